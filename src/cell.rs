@@ -0,0 +1,160 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+use crate::strip::escape_len;
+use crate::style::Style;
+
+use core::ops::Range;
+use std::string::String;
+use unicode_width::UnicodeWidthChar;
+
+//Tab stops are every eight columns, as on a terminal.
+pub(crate) const TAB_WIDTH: usize = 8;
+
+//xterm keeps this many combining marks per cell and drops any further ones.
+const MAX_COMBINING: usize = 2;
+
+///One character cell, a character along with its combining marks and style.
+///
+///A double-width character takes up two cells, the second of which is a spacer of width 0
+///that holds no character of its own.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Cell {
+    c: char,
+    combining: [Option<char>; MAX_COMBINING],
+    width: u8,
+    pub(crate) style: Style,
+}
+
+impl Cell {
+    pub fn new(c: char, style: Style) -> Self {
+        let width = if c.width() == Some(2) { 2 } else { 1 };
+        Cell {
+            c,
+            combining: [None; MAX_COMBINING],
+            width,
+            style,
+        }
+    }
+
+    pub fn c(&self) -> char {
+        self.c
+    }
+
+    ///The combining marks drawn over the character.
+    pub fn combining(&self) -> impl Iterator<Item = char> + '_ {
+        self.combining.iter().flatten().copied()
+    }
+
+    ///How many columns the character takes up, 0 for the spacer behind a wide character.
+    pub fn width(&self) -> usize {
+        self.width as usize
+    }
+
+    pub fn is_spacer(&self) -> bool {
+        self.width == 0
+    }
+
+    pub fn style(&self) -> Style {
+        self.style
+    }
+
+    ///Appends the character and its combining marks to `text`, nothing for a spacer.
+    pub fn push_to(&self, text: &mut String) {
+        if !self.is_spacer() {
+            text.push(self.c);
+            text.extend(self.combining());
+        }
+    }
+
+    //An erased cell keeps the background colour of the pen and nothing else.
+    pub(crate) fn blank(pen: Style) -> Self {
+        Cell::new(
+            ' ',
+            Style {
+                bg: pen.bg,
+                ..Style::default()
+            },
+        )
+    }
+
+    pub(crate) fn is_blank(&self) -> bool {
+        self.c == ' ' && self.width == 1 && self.combining[0].is_none() && self.style.is_default()
+    }
+
+    pub(crate) fn spacer(style: Style) -> Self {
+        Cell {
+            c: ' ',
+            combining: [None; MAX_COMBINING],
+            width: 0,
+            style,
+        }
+    }
+
+    pub(crate) fn combine(&mut self, mark: char) {
+        if let Some(slot) = self.combining.iter_mut().find(|slot| slot.is_none()) {
+            *slot = Some(mark);
+        }
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell::new(' ', Style::default())
+    }
+}
+
+//Takes the next character off a text block, skipping any escape sequence that the parser did
+//not know and left in the text, as those do not draw anything.
+pub(crate) fn next_char(text: &mut &str) -> Option<char> {
+    while text.starts_with('\u{1b}') {
+        *text = &text[escape_len(text).0..];
+    }
+    let c = text.chars().next()?;
+    *text = &text[c.len_utf8()..];
+    Some(c)
+}
+
+//Writes `c` to `line` at `col`, a wide character with a spacer after it. The line has to be
+//long enough for both.
+pub(crate) fn put(line: &mut [Cell], col: usize, c: char, style: Style) {
+    let cell = Cell::new(c, style);
+    set(line, col, cell);
+    if cell.width() == 2 {
+        set(line, col + 1, Cell::spacer(style));
+    }
+}
+
+//Adds a combining mark to the character at `col`, which is the wide character itself when
+//`col` is its spacer.
+pub(crate) fn combine(line: &mut [Cell], mut col: usize, mark: char) {
+    if line[col].is_spacer() && col > 0 {
+        col -= 1;
+    }
+    line[col].combine(mark);
+}
+
+//Overwriting either half of a wide character blanks the other half.
+fn set(line: &mut [Cell], col: usize, cell: Cell) {
+    if line[col].width() == 2 && col + 1 < line.len() && cell.width() != 2 {
+        line[col + 1] = Cell::blank(line[col + 1].style);
+    }
+    if line[col].is_spacer() && col > 0 && !cell.is_spacer() {
+        line[col - 1] = Cell::blank(line[col - 1].style);
+    }
+    line[col] = cell;
+}
+
+//Fills `cols` of `line` with `blank`. Erasing half of a wide character erases all of it.
+pub(crate) fn erase(line: &mut [Cell], cols: Range<usize>, blank: Cell) {
+    if cols.start > 0 && cols.start < line.len() && line[cols.start].is_spacer() {
+        line[cols.start - 1] = blank;
+    }
+    if cols.end < line.len() && line[cols.end].is_spacer() {
+        line[cols.end] = blank;
+    }
+    for cell in &mut line[cols] {
+        *cell = blank;
+    }
+}
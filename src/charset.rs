@@ -0,0 +1,268 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+#[cfg(test)]
+mod tests;
+
+use crate::enums::AnsiSequence;
+#[cfg(any(feature = "std", test))]
+use crate::enums::Output;
+#[cfg(any(feature = "std", test))]
+use crate::traits::AnsiParseIterator;
+
+use core::fmt::{Display, Formatter, Result as DisplayResult};
+#[cfg(any(feature = "std", test))]
+use core::marker::PhantomData;
+#[cfg(any(feature = "std", test))]
+use std::borrow::Cow;
+#[cfg(any(feature = "std", test))]
+use std::string::String;
+
+const SHIFT_OUT: char = '\u{e}';
+const SHIFT_IN: char = '\u{f}';
+
+///A character set that can be designated into G0–G3.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Charset {
+    ///US ASCII, designated with `B`.
+    #[default]
+    Ascii,
+    ///The UK set, which is ASCII with `£` in place of `#`, designated with `A`.
+    Uk,
+    ///DEC Special Graphics, the line drawing set, designated with `0`.
+    DecSpecialGraphics,
+    ///DEC Supplemental, the upper half of the DEC Multinational set, designated with `<`.
+    DecSupplemental,
+}
+
+//`_` through `~` in DEC Special Graphics.
+const SPECIAL_GRAPHICS: [char; 32] = [
+    '\u{a0}', '◆', '▒', '␉', '␌', '␍', '␊', '°', '±', '␤', '␋', '┘', '┐', '┌', '└', '┼', '⎺', '⎻',
+    '─', '⎼', '⎽', '├', '┤', '┴', '┬', '│', '≤', '≥', 'π', '≠', '£', '·',
+];
+
+impl Charset {
+    ///The final character that designates this set.
+    pub fn designator(self) -> char {
+        match self {
+            Charset::Ascii => 'B',
+            Charset::Uk => 'A',
+            Charset::DecSpecialGraphics => '0',
+            Charset::DecSupplemental => '<',
+        }
+    }
+
+    ///Maps a character as sent to the Unicode character this set shows for it. Only the
+    ///printable ASCII characters are affected.
+    pub fn map(self, c: char) -> char {
+        match self {
+            Charset::Ascii => c,
+            Charset::Uk if c == '#' => '£',
+            Charset::Uk => c,
+            Charset::DecSpecialGraphics => match c {
+                '_'..='~' => SPECIAL_GRAPHICS[c as usize - '_' as usize],
+                c => c,
+            },
+            //This is Latin-1 moved down by 0x80, except for the few places DEC differs.
+            Charset::DecSupplemental => match c {
+                '!'..='~' => match c as u32 + 0x80 {
+                    0xa8 => '¤',
+                    0xd7 => 'Œ',
+                    0xdd => 'Ÿ',
+                    0xf7 => 'œ',
+                    0xfd => 'ÿ',
+                    0xa4 | 0xa6 | 0xac..=0xaf | 0xb4 | 0xb8 | 0xbe | 0xd0 | 0xde | 0xf0 | 0xfe => {
+                        '\u{fffd}'
+                    }
+                    code => core::char::from_u32(code).unwrap_or(c),
+                },
+                c => c,
+            },
+        }
+    }
+}
+
+impl Display for Charset {
+    fn fmt(&self, formatter: &mut Formatter) -> DisplayResult {
+        write!(formatter, "{}", self.designator())
+    }
+}
+
+///The designated G0–G3 sets and which of them is shifted into the graphic left half, as set
+///by the designation sequences and by SI, SO, LS2 and LS3. A single shift, SS2 or SS3, uses G2
+///or G3 for the next graphic character only.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct CharsetState {
+    sets: [Charset; 4],
+    gl: usize,
+    single: Option<usize>,
+}
+
+impl CharsetState {
+    pub fn new() -> Self {
+        CharsetState::default()
+    }
+
+    ///The set designated into G0–G3, `None` for any other slot.
+    pub fn designated(&self, slot: usize) -> Option<Charset> {
+        self.sets.get(slot).copied()
+    }
+
+    ///Which of G0–G3 is used for text.
+    pub fn shifted(&self) -> usize {
+        self.gl
+    }
+
+    ///The set text is currently drawn in, leaving out a pending single shift.
+    pub fn active(&self) -> Charset {
+        self.sets[self.gl]
+    }
+
+    ///G2 or G3 when the next graphic character is drawn from it by a single shift.
+    pub fn single_shift(&self) -> Option<usize> {
+        self.single
+    }
+
+    ///Updates the state from a sequence, anything that is not about character sets is ignored.
+    ///The alternate ROM sets are taken as their standard counterparts.
+    pub fn apply(&mut self, seq: &AnsiSequence) {
+        use AnsiSequence::*;
+
+        match *seq {
+            SetUSG0 | SetG0AlternateChar => self.sets[0] = Charset::Ascii,
+            SetUSG1 | SetG1AlternateChar => self.sets[1] = Charset::Ascii,
+            SetUKG0 => self.sets[0] = Charset::Uk,
+            SetUKG1 => self.sets[1] = Charset::Uk,
+            SetG0SpecialChars | SetG0AltAndSpecialGraph => {
+                self.sets[0] = Charset::DecSpecialGraphics
+            }
+            SetG1SpecialChars | SetG1AltAndSpecialGraph => {
+                self.sets[1] = Charset::DecSpecialGraphics
+            }
+            SetG0DecSupplemental => self.sets[0] = Charset::DecSupplemental,
+            SetG1DecSupplemental => self.sets[1] = Charset::DecSupplemental,
+            SetG2Charset(set) => self.sets[2] = set,
+            SetG3Charset(set) => self.sets[3] = set,
+            LockShiftG2 => self.gl = 2,
+            LockShiftG3 => self.gl = 3,
+            SetSingleShift2 => self.single = Some(2),
+            SetSingleShift3 => self.single = Some(3),
+            _ => {}
+        }
+    }
+
+    ///Handles SI and SO, which come as control characters in the text. Returns whether `c`
+    ///was one of them.
+    pub fn shift(&mut self, c: char) -> bool {
+        match c {
+            SHIFT_IN => self.gl = 0,
+            SHIFT_OUT => self.gl = 1,
+            _ => return false,
+        }
+        true
+    }
+
+    ///Maps a character through the active set. A graphic character uses up a pending single
+    ///shift, control characters leave it for the next one.
+    pub fn map(&mut self, c: char) -> char {
+        if c.is_control() {
+            return c;
+        }
+        match self.single.take() {
+            Some(slot) => self.sets[slot].map(c),
+            None => self.active().map(c),
+        }
+    }
+
+    ///Maps a text block to what it shows, dropping SI and SO and following them. The text is
+    ///borrowed when nothing changes.
+    #[cfg(any(feature = "std", test))]
+    pub fn translate<'t>(&mut self, text: &'t str) -> Cow<'t, str> {
+        if self.active() == Charset::Ascii
+            && self.single.is_none()
+            && !text.contains(&[SHIFT_IN, SHIFT_OUT][..])
+        {
+            return Cow::Borrowed(text);
+        }
+
+        let mut out: Option<String> = None;
+        for (pos, c) in text.char_indices() {
+            let mapped = if self.shift(c) {
+                None
+            } else {
+                Some(self.map(c))
+            };
+            if mapped != Some(c) && out.is_none() {
+                out = Some(String::from(&text[..pos]));
+            }
+            if let (Some(out), Some(mapped)) = (&mut out, mapped) {
+                out.push(mapped);
+            }
+        }
+
+        match out {
+            Some(out) => Cow::Owned(out),
+            None => Cow::Borrowed(text),
+        }
+    }
+}
+
+///Yields the text of an [`Output`] stream as it shows with the designated character sets, so
+///that `ESC(0` followed by `lqqk` comes out as `┌──┐`.
+#[cfg(any(feature = "std", test))]
+#[derive(Debug)]
+pub struct CharsetIterator<'a, I> {
+    inner: I,
+    state: CharsetState,
+    _marker: PhantomData<Output<'a>>,
+}
+
+#[cfg(any(feature = "std", test))]
+impl<'a, I> CharsetIterator<'a, I>
+where
+    I: Iterator<Item = Output<'a>>,
+{
+    pub fn new(inner: I) -> Self {
+        CharsetIterator {
+            inner,
+            state: CharsetState::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn state(&self) -> &CharsetState {
+        &self.state
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+impl<'a, I> Iterator for CharsetIterator<'a, I>
+where
+    I: Iterator<Item = Output<'a>>,
+{
+    type Item = Cow<'a, str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.inner.next()? {
+                Output::TextBlock(text) => {
+                    let text = self.state.translate(text);
+                    //A block of nothing but shifts.
+                    if !text.is_empty() {
+                        return Some(text);
+                    }
+                }
+                Output::Escape(seq) => self.state.apply(&seq),
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "std", test))]
+impl<'a> AnsiParseIterator<'a> {
+    ///Translates the text through the designated character sets, see [`CharsetIterator`].
+    pub fn translated(self) -> CharsetIterator<'a, Self> {
+        CharsetIterator::new(self)
+    }
+}
@@ -0,0 +1,151 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+use super::*;
+use crate::input::{parse_key, KeyCode, KeyEvent, Modifiers};
+use crate::traits::AnsiParser;
+
+use std::vec::Vec;
+
+fn translated(input: &str) -> Vec<Cow<'_, str>> {
+    input.ansi_parse().translated().collect()
+}
+
+#[test]
+fn test_ascii_is_borrowed() {
+    let out = translated("plain #text");
+    assert_eq!(out, vec!["plain #text"]);
+    assert!(matches!(out[0], Cow::Borrowed(_)));
+}
+
+#[test]
+fn test_line_drawing() {
+    assert_eq!(
+        translated("\x1b(0lqqk\r\nx  x\r\nmqqj\x1b(B ok"),
+        vec!["┌──┐\r\n│  │\r\n└──┘", " ok"]
+    );
+}
+
+#[test]
+fn test_uk() {
+    assert_eq!(translated("\x1b(A#1 = 100"), vec!["£1 = 100"]);
+}
+
+#[test]
+fn test_dec_supplemental() {
+    assert_eq!(translated("\x1b(<1AWg"), vec!["±ÁŒç"]);
+    assert_eq!(Charset::DecSupplemental.map('$'), '\u{fffd}');
+    assert_eq!(Charset::DecSupplemental.map(' '), ' ');
+}
+
+#[test]
+fn test_special_graphics_leaves_other_characters() {
+    assert_eq!(translated("\x1b(0ABC 123 é`~_"), vec!["ABC 123 é◆·\u{a0}"]);
+}
+
+#[test]
+fn test_alternate_roms_are_standard_sets() {
+    assert_eq!(translated("\x1b(2q\x1b(1q"), vec!["─", "q"]);
+}
+
+#[test]
+fn test_shift_in_and_out() {
+    assert_eq!(translated("\x1b)0a\u{e}q\u{f}a"), vec!["a─a"]);
+    //Only the shifts change, so the block is not borrowed.
+    assert!(matches!(
+        translated("a\u{e}a").as_slice(),
+        [Cow::Owned(s)] if s == "aa"
+    ));
+    assert!(translated("\u{e}\u{f}").is_empty());
+}
+
+#[test]
+fn test_shift_spans_blocks() {
+    assert_eq!(translated("\x1b)0\u{e}x\x1b[1mx\u{f}x"), vec!["│", "│x"]);
+}
+
+#[test]
+fn test_lock_shifts() {
+    let mut state = CharsetState::new();
+    state.apply(&AnsiSequence::SetG2Charset(Charset::Uk));
+    state.apply(&AnsiSequence::SetG3Charset(Charset::DecSpecialGraphics));
+    assert_eq!(state.translate("#q"), "#q");
+    state.apply(&AnsiSequence::LockShiftG2);
+    assert_eq!(state.shifted(), 2);
+    assert_eq!(state.translate("#q"), "£q");
+    state.apply(&AnsiSequence::LockShiftG3);
+    assert_eq!(state.translate("#q"), "#─");
+    assert_eq!(state.translate("\u{f}#q"), "#q");
+    assert_eq!(state.shifted(), 0);
+}
+
+#[test]
+fn test_designations() {
+    let mut state = CharsetState::new();
+    for seq in "\x1b(A\x1b)0\x1b*<\x1b+A".ansi_parse() {
+        if let Output::Escape(seq) = seq {
+            state.apply(&seq);
+        }
+    }
+    assert_eq!(state.designated(0), Some(Charset::Uk));
+    assert_eq!(state.designated(1), Some(Charset::DecSpecialGraphics));
+    assert_eq!(state.designated(2), Some(Charset::DecSupplemental));
+    assert_eq!(state.designated(3), Some(Charset::Uk));
+    assert_eq!(state.designated(4), None);
+}
+
+#[test]
+fn test_display() {
+    assert_eq!(
+        std::format!("{}", AnsiSequence::SetG3Charset(Charset::DecSupplemental)),
+        "\u{1b}+<"
+    );
+}
+
+#[test]
+fn test_single_shift_applies_to_one_character() {
+    assert_eq!(
+        translated("\x1b*0\x1b+Aa\x1bNqq\x1bO#q#"),
+        vec!["a", "─q", "£q#"]
+    );
+}
+
+#[test]
+fn test_single_shift_skips_controls() {
+    assert_eq!(translated("\x1b*0\x1bN\r\nqq"), vec!["\r\n─q"]);
+}
+
+#[test]
+fn test_single_shift_over_locking_shift() {
+    let mut state = CharsetState::new();
+    state.apply(&AnsiSequence::SetG1SpecialChars);
+    assert_eq!(state.translate("\u{e}"), "");
+    state.apply(&AnsiSequence::SetSingleShift2);
+    assert_eq!(state.single_shift(), Some(2));
+    assert_eq!(state.translate("qq"), "q─");
+    assert_eq!(state.single_shift(), None);
+    assert_eq!(state.shifted(), 1);
+}
+
+#[test]
+fn test_ss3_in_output_and_input() {
+    //In output `ESC O A` is an `A` from G3, typed by a user it is the up arrow.
+    let mut state = CharsetState::new();
+    state.apply(&AnsiSequence::SetG3Charset(Charset::DecSupplemental));
+    let out: Vec<Cow<'_, str>> = "\x1bOA"
+        .ansi_parse()
+        .filter_map(|output| match output {
+            Output::Escape(seq) => {
+                state.apply(&seq);
+                None
+            }
+            Output::TextBlock(text) => Some(state.translate(text)),
+        })
+        .collect();
+    assert_eq!(out, vec!["Á"]);
+
+    let (rest, key) = parse_key("\x1bOA", false).unwrap();
+    assert_eq!(rest, "");
+    assert_eq!(key, KeyEvent::new(KeyCode::Up, Modifiers::empty()));
+}
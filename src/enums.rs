@@ -5,6 +5,8 @@
 #[cfg(test)]
 mod tests;
 
+use crate::charset::Charset;
+
 use heapless::{consts::U5, Vec};
 
 ///The following are the implemented ANSI escape sequences. More to be added.
@@ -20,6 +22,7 @@ pub enum AnsiSequence {
     CursorRestore,
     EraseDisplay,
     EraseLine,
+    EraseScrollback,
     SetGraphicsMode(Vec<u8, U5>),
     SetMode(u8),
     ResetMode(u8),
@@ -56,9 +59,66 @@ pub enum AnsiSequence {
     SetG1AlternateChar,
     SetG0AltAndSpecialGraph,
     SetG1AltAndSpecialGraph,
+    SetG0DecSupplemental,
+    SetG1DecSupplemental,
+    SetG2Charset(Charset),
+    SetG3Charset(Charset),
+    ///LS2, shifts G2 in for text until SI or SO.
+    LockShiftG2,
+    ///LS3, shifts G3 in for text until SI or SO.
+    LockShiftG3,
+    ///SS2, draws the next graphic character from G2.
     SetSingleShift2,
+    ///SS3, draws the next graphic character from G3.
+    ///
+    ///This is what `ESC O` means in output. In input the same two characters start the
+    ///application mode cursor and function keys, so `ESC O A` typed by a user is the up arrow
+    ///and not an `A` from G3. Keystrokes have to go through [`parse_key`](crate::parse_key) or
+    ///[`parse_input`](crate::parse_input), never through the output parser.
     SetSingleShift3,
     SetTopAndBottom(u32, u32),
+    ///Pushes the kitty keyboard protocol flags, a bit set of which only the low five bits are
+    ///defined.
+    PushKeyboardFlags(u8),
+    ///Pops this many entries off the kitty keyboard flags stack, 1 when left out. It is a count
+    ///and not a set of flags, so unlike the flags it is not limited to a byte.
+    PopKeyboardFlags(u32),
+    QueryKeyboardFlags,
+    SetKeyModifierOption(u8, u8),
+    ResetKeyModifierOption(u8),
+    QueryKeyModifierOption(u8),
+    SetBracketedPaste,
+    ResetBracketedPaste,
+    PasteStart,
+    PasteEnd,
+    SetFocusReporting,
+    ResetFocusReporting,
+    ///Reports button presses and releases, `?1000`.
+    SetMouseTracking,
+    ResetMouseTracking,
+    ///Also reports motion while a button is held down, `?1002`.
+    SetButtonEventTracking,
+    ResetButtonEventTracking,
+    ///Reports all motion, `?1003`.
+    SetAnyEventTracking,
+    ResetAnyEventTracking,
+    ///Sends mouse reports in the SGR format, `?1006`.
+    SetSgrMouse,
+    ResetSgrMouse,
+    FocusIn,
+    FocusOut,
+    SetSynchronizedUpdate,
+    ResetSynchronizedUpdate,
+    SetAlternateScreen,
+    ResetAlternateScreen,
+    SetAlternateScreenClear,
+    ResetAlternateScreenClear,
+    SetAlternateScreenSaveCursor,
+    ResetAlternateScreenSaveCursor,
+    ///DECSC, which saves the pen and modes along with the position unlike `CursorSave`.
+    SaveCursor,
+    ///DECRC, restoring what `SaveCursor` saved.
+    RestoreCursor,
 }
 
 use core::fmt::{Display, Formatter, Result as DisplayResult};
@@ -69,27 +129,32 @@ impl Display for AnsiSequence {
         use AnsiSequence::*;
         match self {
             Escape => write!(formatter, "\u{1b}"),
+            //Parameters of 1 are the defaults and left out, as most programs write them.
+            CursorPos(1, 1) => write!(formatter, "[H"),
             CursorPos(line, col) => write!(formatter, "[{};{}H", line, col),
+            CursorUp(1) => write!(formatter, "[A"),
             CursorUp(amt) => write!(formatter, "[{}A", amt),
+            CursorDown(1) => write!(formatter, "[B"),
             CursorDown(amt) => write!(formatter, "[{}B", amt),
+            CursorForward(1) => write!(formatter, "[C"),
             CursorForward(amt) => write!(formatter, "[{}C", amt),
+            CursorBackward(1) => write!(formatter, "[D"),
             CursorBackward(amt) => write!(formatter, "[{}D", amt),
             CursorSave => write!(formatter, "[s"),
             CursorRestore => write!(formatter, "[u"),
             EraseDisplay => write!(formatter, "[2J"),
             EraseLine => write!(formatter, "[K"),
-            SetGraphicsMode(vec) => match vec.len() {
-                0 => write!(formatter, "[m"),
-                1 => write!(formatter, "[{}m", vec[0]),
-                2 => write!(formatter, "[{};{}m", vec[0], vec[1]),
-                3 => write!(formatter, "[{};{};{}m", vec[0], vec[1], vec[2]),
-                5 => write!(
-                    formatter,
-                    "[{};{};{};{};{}m",
-                    vec[0], vec[1], vec[2], vec[3], vec[4]
-                ),
-                _ => unreachable!(),
-            },
+            EraseScrollback => write!(formatter, "[3J"),
+            SetGraphicsMode(vec) => {
+                write!(formatter, "[")?;
+                for (i, val) in vec.iter().enumerate() {
+                    if i > 0 {
+                        write!(formatter, ";")?;
+                    }
+                    write!(formatter, "{}", val)?;
+                }
+                write!(formatter, "m")
+            }
             SetMode(mode) => write!(formatter, "[={}h", mode),
             ResetMode(mode) => write!(formatter, "[={}l", mode),
             ShowCursor => write!(formatter, "[?25h"),
@@ -125,9 +190,47 @@ impl Display for AnsiSequence {
             SetG1AlternateChar => write!(formatter, ")1"),
             SetG0AltAndSpecialGraph => write!(formatter, "(2"),
             SetG1AltAndSpecialGraph => write!(formatter, ")2"),
+            SetG0DecSupplemental => write!(formatter, "(<"),
+            SetG1DecSupplemental => write!(formatter, ")<"),
+            SetG2Charset(set) => write!(formatter, "*{}", set),
+            SetG3Charset(set) => write!(formatter, "+{}", set),
+            LockShiftG2 => write!(formatter, "n"),
+            LockShiftG3 => write!(formatter, "o"),
             SetSingleShift2 => write!(formatter, "N"),
             SetSingleShift3 => write!(formatter, "O"),
-            SetTopAndBottom(x, y) => write!(formatter, "{};{}r", x, y),
+            SetTopAndBottom(x, y) => write!(formatter, "[{};{}r", x, y),
+            PushKeyboardFlags(flags) => write!(formatter, "[>{}u", flags),
+            PopKeyboardFlags(amt) => write!(formatter, "[<{}u", amt),
+            QueryKeyboardFlags => write!(formatter, "[?u"),
+            SetKeyModifierOption(res, val) => write!(formatter, "[>{};{}m", res, val),
+            ResetKeyModifierOption(res) => write!(formatter, "[>{}m", res),
+            QueryKeyModifierOption(res) => write!(formatter, "[?{}m", res),
+            SetBracketedPaste => write!(formatter, "[?2004h"),
+            ResetBracketedPaste => write!(formatter, "[?2004l"),
+            PasteStart => write!(formatter, "[200~"),
+            PasteEnd => write!(formatter, "[201~"),
+            SetFocusReporting => write!(formatter, "[?1004h"),
+            ResetFocusReporting => write!(formatter, "[?1004l"),
+            SetMouseTracking => write!(formatter, "[?1000h"),
+            ResetMouseTracking => write!(formatter, "[?1000l"),
+            SetButtonEventTracking => write!(formatter, "[?1002h"),
+            ResetButtonEventTracking => write!(formatter, "[?1002l"),
+            SetAnyEventTracking => write!(formatter, "[?1003h"),
+            ResetAnyEventTracking => write!(formatter, "[?1003l"),
+            SetSgrMouse => write!(formatter, "[?1006h"),
+            ResetSgrMouse => write!(formatter, "[?1006l"),
+            FocusIn => write!(formatter, "[I"),
+            FocusOut => write!(formatter, "[O"),
+            SetSynchronizedUpdate => write!(formatter, "[?2026h"),
+            ResetSynchronizedUpdate => write!(formatter, "[?2026l"),
+            SetAlternateScreen => write!(formatter, "[?47h"),
+            ResetAlternateScreen => write!(formatter, "[?47l"),
+            SetAlternateScreenClear => write!(formatter, "[?1047h"),
+            ResetAlternateScreenClear => write!(formatter, "[?1047l"),
+            SetAlternateScreenSaveCursor => write!(formatter, "[?1049h"),
+            ResetAlternateScreenSaveCursor => write!(formatter, "[?1049l"),
+            SaveCursor => write!(formatter, "7"),
+            RestoreCursor => write!(formatter, "8"),
         }
     }
 }
@@ -0,0 +1,175 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+#[cfg(test)]
+mod tests;
+
+use crate::cell::{self, next_char, Cell, TAB_WIDTH};
+use crate::enums::{AnsiSequence, Output};
+use crate::style::{Style, StyleState};
+
+use core::fmt::{Result as DisplayResult, Write};
+use std::vec::Vec;
+use unicode_width::UnicodeWidthChar;
+
+//The right margin for cursor movement, text written past it still goes on the line.
+const MAX_COL: usize = 4096;
+
+//Every line written so far, with the cursor somewhere among them.
+#[derive(Debug)]
+struct LineBuffer {
+    lines: Vec<Vec<Cell>>,
+    row: usize,
+    col: usize,
+    saved: (usize, usize),
+    pen: StyleState,
+}
+
+impl LineBuffer {
+    fn new() -> Self {
+        LineBuffer {
+            lines: vec![Vec::new()],
+            row: 0,
+            col: 0,
+            saved: (0, 0),
+            pen: StyleState::new(),
+        }
+    }
+
+    fn line(&mut self) -> &mut Vec<Cell> {
+        if self.row >= self.lines.len() {
+            self.lines.resize_with(self.row + 1, Vec::new);
+        }
+        &mut self.lines[self.row]
+    }
+
+    //The cursor moves by display columns, a wide character taking up a cell and a spacer
+    //after it as on a `Screen`.
+    fn put(&mut self, c: char) {
+        let width = match c.width() {
+            Some(0) | None => return self.combine(c),
+            Some(width) => width,
+        };
+
+        let (col, style) = (self.col, self.pen.style());
+        let line = self.line();
+        if line.len() < col + width {
+            line.resize(col + width, Cell::default());
+        }
+        cell::put(line, col, c, style);
+        self.col += width;
+    }
+
+    //A combining mark goes on the character before the cursor, if there is one.
+    fn combine(&mut self, mark: char) {
+        let col = self.col;
+        let line = self.line();
+        match col.checked_sub(1) {
+            Some(col) if col < line.len() => cell::combine(line, col, mark),
+            _ => {}
+        }
+    }
+
+    fn text(&mut self, mut text: &str) {
+        while let Some(c) = next_char(&mut text) {
+            match c {
+                '\n' => {
+                    self.row += 1;
+                    self.col = 0;
+                    self.line();
+                }
+                '\r' => self.col = 0,
+                '\u{8}' => self.col = self.col.saturating_sub(1),
+                '\t' => self.col = (self.col / TAB_WIDTH + 1) * TAB_WIDTH,
+                c if c.is_control() => {}
+                c => self.put(c),
+            }
+        }
+    }
+
+    fn escape(&mut self, seq: &AnsiSequence) {
+        use AnsiSequence::*;
+
+        match *seq {
+            SetGraphicsMode(_) => self.pen.apply(seq),
+            EraseLine => {
+                let col = self.col;
+                let line = self.line();
+                if col < line.len() {
+                    let end = line.len();
+                    cell::erase(line, col..end, Cell::default());
+                    line.truncate(col);
+                }
+            }
+            CursorUp(n) => self.row = self.row.saturating_sub(n as usize),
+            //Clamped, so that a huge move can not take up memory without bound.
+            CursorDown(n) => {
+                self.row = self
+                    .row
+                    .saturating_add(n as usize)
+                    .min(self.lines.len() - 1);
+            }
+            CursorForward(n) => {
+                self.col = self
+                    .col
+                    .saturating_add(n as usize)
+                    .min(self.col.max(MAX_COL));
+            }
+            CursorBackward(n) => self.col = self.col.saturating_sub(n as usize),
+            CursorSave => self.saved = (self.row, self.col),
+            CursorRestore => {
+                let (row, col) = self.saved;
+                self.row = row;
+                self.col = col;
+            }
+            _ => {}
+        }
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> DisplayResult {
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 {
+                writer.write_char('\n')?;
+            }
+
+            let mut pen = Style::default();
+            for cell in line.iter().filter(|cell| !cell.is_spacer()) {
+                pen.write_diff(&cell.style(), writer)?;
+                pen = cell.style();
+                writer.write_char(cell.c())?;
+                for mark in cell.combining() {
+                    writer.write_char(mark)?;
+                }
+            }
+            pen.write_diff(&Style::default(), writer)?;
+        }
+        Ok(())
+    }
+}
+
+///Writes the final visible state of every line of `outputs` to `writer`, for logs of programs
+///that redraw progress bars in place.
+///
+///Carriage returns, backspaces, tabs, [`EraseLine`](AnsiSequence::EraseLine) and relative
+///cursor movement are played back on a buffer of lines, cursor up moving back to lines that
+///were already written. As at the bottom of a terminal, cursor down stops at the last line,
+///and cursor forward stops at a right margin far wider than any terminal. Styling is kept,
+///each line starting and ending with the default style so lines can be read on their own.
+///Other sequences and control characters are dropped.
+pub fn flatten<'a, I, W>(outputs: I, writer: &mut W) -> DisplayResult
+where
+    I: IntoIterator<Item = Output<'a>>,
+    W: Write,
+{
+    let mut buffer = LineBuffer::new();
+
+    for output in outputs {
+        match output {
+            Output::TextBlock(text) => buffer.text(text),
+            Output::Escape(seq) => buffer.escape(&seq),
+        }
+    }
+
+    buffer.write(writer)
+}
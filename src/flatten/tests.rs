@@ -0,0 +1,123 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+use super::*;
+use crate::traits::AnsiParser;
+
+fn flat(input: &str) -> String {
+    let mut out = String::new();
+    flatten(input.ansi_parse(), &mut out).unwrap();
+    out
+}
+
+#[test]
+fn test_plain_lines() {
+    assert_eq!(flat("one\ntwo\n"), "one\ntwo\n");
+    assert_eq!(flat("no newline"), "no newline");
+    assert_eq!(flat(""), "");
+}
+
+#[test]
+fn test_carriage_return_overwrites() {
+    assert_eq!(
+        flat("Downloading  10%\rDownloading  55%\rDownloading 100%\ndone\n"),
+        "Downloading 100%\ndone\n"
+    );
+    //Shorter text only overwrites the start of the line.
+    assert_eq!(flat("abcdef\rxy\n"), "xycdef\n");
+}
+
+#[test]
+fn test_erase_line() {
+    assert_eq!(flat("abcdef\r\x1b[Kxy\n"), "xy\n");
+    assert_eq!(flat("abcdef\x1b[3D\x1b[K!"), "abc!");
+}
+
+#[test]
+fn test_backspace() {
+    assert_eq!(flat("ab\u{8}\u{8}xy"), "xy");
+    assert_eq!(flat("\u{8}\u{8}a"), "a");
+}
+
+#[test]
+fn test_cursor_up_rewrites_earlier_lines() {
+    //As docker pull redraws one line per layer.
+    let input = "layer1: Waiting\nlayer2: Waiting\n\
+                 \x1b[2A\r\x1b[Klayer1: Pull complete\n\
+                 \x1b[1B\x1b[1A\r\x1b[Klayer2: Downloading\n\
+                 \x1b[1A\r\x1b[Klayer2: Pull complete\n";
+    assert_eq!(
+        flat(input),
+        "layer1: Pull complete\nlayer2: Pull complete\n"
+    );
+}
+
+#[test]
+fn test_keeps_styling() {
+    assert_eq!(
+        flat("\x1b[32m 50%\x1b[0m\r\x1b[32m100%\x1b[0m ok\n"),
+        "\x1b[32m100%\x1b[m ok\n"
+    );
+    //A style that runs over a newline is closed at the end of the line and opened again.
+    assert_eq!(
+        flat("\x1b[1mab\ncd\x1b[22m\n"),
+        "\x1b[1mab\x1b[m\n\x1b[1mcd\x1b[m\n"
+    );
+}
+
+#[test]
+fn test_long_style_change_parses_back() {
+    //Every attribute at once takes more parameters than one sequence can carry.
+    let input = "\x1b[1;2;3;4;5m\x1b[7;8;9m\x1b[38;2;1;2;3m\x1b[48;2;4;5;6mrich\x1b[m\n";
+    let out = flat(input);
+    let runs: Vec<_> = out.ansi_parse().styled().collect();
+    assert_eq!(runs, input.ansi_parse().styled().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_overwritten_style_is_dropped() {
+    assert_eq!(flat("\x1b[31mred\x1b[0m\rblue"), "blue");
+}
+
+#[test]
+fn test_tab_and_gaps() {
+    assert_eq!(flat("a\tb"), "a       b");
+    assert_eq!(flat("a\x1b[3Cb"), "a   b");
+}
+
+#[test]
+fn test_drops_other_sequences() {
+    assert_eq!(flat("\x1b[?25la\x07b\x1b]0;title\x07c\x1b[?25h\n"), "abc\n");
+}
+
+#[test]
+fn test_save_restore() {
+    assert_eq!(flat("start \x1b[s....\n\x1b[udone"), "start done\n");
+}
+
+#[test]
+fn test_wide_characters() {
+    assert_eq!(flat("中中\rab\n"), "ab中\n");
+    assert_eq!(flat("中中\ra\n"), "a 中\n");
+    //Half of the second character is overwritten.
+    assert_eq!(flat("中中\r\x1b[3Cx\n"), "中 x\n");
+    assert_eq!(flat("ab\r中\n"), "中\n");
+    assert_eq!(flat("中文 50%\r中文 100%\n"), "中文 100%\n");
+    assert_eq!(flat("中中\x1b[3D\x1b[K!"), " !");
+}
+
+#[test]
+fn test_combining_marks() {
+    assert_eq!(flat("e\u{301}x\rab\n"), "ab\n");
+    assert_eq!(flat("ae\u{301}\rx\n"), "xe\u{301}\n");
+}
+
+#[test]
+fn test_huge_moves_are_clamped() {
+    assert_eq!(flat("a\x1b[4000000000Bb\n"), "ab\n");
+    assert_eq!(flat("a\nb\x1b[1A\x1b[4000000000B\rc"), "a\nc");
+    let out = flat("a\x1b[4000000000Cb");
+    assert_eq!(out.len(), MAX_COL + 1);
+    assert!(out.ends_with(" b"));
+}
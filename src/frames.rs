@@ -0,0 +1,157 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+#[cfg(test)]
+mod tests;
+
+use crate::enums::{AnsiSequence, Output};
+use crate::traits::AnsiParseIterator;
+
+use core::fmt::{Display, Formatter, Result as DisplayResult};
+use core::time::Duration;
+
+///A group of outputs that make up one screen update.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Frame<'a>(pub Vec<Output<'a>>);
+
+impl<'a> Display for Frame<'a> {
+    fn fmt(&self, formatter: &mut Formatter) -> DisplayResult {
+        for output in &self.0 {
+            write!(formatter, "{}", output)?;
+        }
+        Ok(())
+    }
+}
+
+///Anything [`FrameIterator`] can group: a bare [`Output`], or an [`Output`] paired with the
+///time it was recorded at, measured from the start of the recording.
+pub trait FrameItem<'a> {
+    fn into_parts(self) -> (Option<Duration>, Output<'a>);
+}
+
+impl<'a> FrameItem<'a> for Output<'a> {
+    fn into_parts(self) -> (Option<Duration>, Output<'a>) {
+        (None, self)
+    }
+}
+
+impl<'a> FrameItem<'a> for (Duration, Output<'a>) {
+    fn into_parts(self) -> (Option<Duration>, Output<'a>) {
+        (Some(self.0), self.1)
+    }
+}
+
+///Groups an [`Output`] stream into frames.
+///
+///Once the stream uses synchronized updates (`?2026`) every frame runs from
+///[`AnsiSequence::SetSynchronizedUpdate`] to [`AnsiSequence::ResetSynchronizedUpdate`], with
+///whatever is written between two updates forming a frame of its own. Until then a new frame
+///starts at every [`AnsiSequence::EraseDisplay`], and at every pause longer than the gap set
+///with [`FrameIterator::with_gap`] when the items carry timestamps.
+#[derive(Debug)]
+pub struct FrameIterator<'a, I> {
+    inner: I,
+    gap: Option<Duration>,
+    synchronized: bool,
+    in_update: bool,
+    last_time: Option<Duration>,
+    pending: Option<(Option<Duration>, Output<'a>)>,
+}
+
+impl<'a, I, T> FrameIterator<'a, I>
+where
+    I: Iterator<Item = T>,
+    T: FrameItem<'a>,
+{
+    pub fn new(inner: I) -> Self {
+        FrameIterator {
+            inner,
+            gap: None,
+            synchronized: false,
+            in_update: false,
+            last_time: None,
+            pending: None,
+        }
+    }
+
+    ///Starts a new frame whenever two timestamped items are further apart than `gap`.
+    pub fn with_gap(mut self, gap: Duration) -> Self {
+        self.gap = Some(gap);
+        self
+    }
+
+    fn starts_frame(&mut self, time: Option<Duration>, output: &Output<'a>) -> bool {
+        if let Output::Escape(AnsiSequence::SetSynchronizedUpdate) = output {
+            self.synchronized = true;
+            return !self.in_update;
+        }
+        if self.synchronized {
+            return false;
+        }
+        if let Output::Escape(AnsiSequence::EraseDisplay) = output {
+            return true;
+        }
+        match (self.gap, self.last_time, time) {
+            (Some(gap), Some(last), Some(time)) => time.saturating_sub(last) > gap,
+            _ => false,
+        }
+    }
+}
+
+impl<'a, I, T> Iterator for FrameIterator<'a, I>
+where
+    I: Iterator<Item = T>,
+    T: FrameItem<'a>,
+{
+    type Item = Frame<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut frame = Vec::new();
+
+        loop {
+            let (time, output) = match self.pending.take() {
+                Some(item) => item,
+                None => match self.inner.next() {
+                    Some(item) => item.into_parts(),
+                    None => break,
+                },
+            };
+
+            if self.starts_frame(time, &output) && !frame.is_empty() {
+                self.pending = Some((time, output));
+                return Some(Frame(frame));
+            }
+
+            if time.is_some() {
+                self.last_time = time;
+            }
+
+            match output {
+                Output::Escape(AnsiSequence::SetSynchronizedUpdate) => {
+                    self.in_update = true;
+                    frame.push(output);
+                }
+                Output::Escape(AnsiSequence::ResetSynchronizedUpdate) if self.in_update => {
+                    self.in_update = false;
+                    frame.push(output);
+                    return Some(Frame(frame));
+                }
+                _ => frame.push(output),
+            }
+        }
+
+        if frame.is_empty() {
+            None
+        } else {
+            Some(Frame(frame))
+        }
+    }
+}
+
+impl<'a> AnsiParseIterator<'a> {
+    ///Groups the parsed output into frames, see [`FrameIterator`].
+    pub fn frames(self) -> FrameIterator<'a, Self> {
+        FrameIterator::new(self)
+    }
+}
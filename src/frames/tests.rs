@@ -0,0 +1,87 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+use super::*;
+use crate::traits::AnsiParser;
+
+fn frame_strings<'a, I>(frames: I) -> Vec<String>
+where
+    I: Iterator<Item = Frame<'a>>,
+{
+    frames.map(|frame| frame.to_string()).collect()
+}
+
+#[test]
+fn test_synchronized_frames() {
+    let frames = frame_strings(
+        "\x1b[?2026hone\x1b[?2026lbetween\x1b[?2026h\x1b[2Jtwo\x1b[?2026l"
+            .ansi_parse()
+            .frames(),
+    );
+
+    assert_eq!(
+        frames,
+        vec![
+            "\x1b[?2026hone\x1b[?2026l",
+            "between",
+            "\x1b[?2026h\x1b[2Jtwo\x1b[?2026l",
+        ]
+    );
+}
+
+#[test]
+fn test_synchronized_frame_split_from_leading_output() {
+    let frames = frame_strings("setup\x1b[?2026hone\x1b[?2026l".ansi_parse().frames());
+    assert_eq!(frames, vec!["setup", "\x1b[?2026hone\x1b[?2026l"]);
+}
+
+#[test]
+fn test_clear_screen_fallback() {
+    let frames = frame_strings(
+        "\x1b[2Jone\x1b[H\x1b[2Jtwo\x1b[2Jthree"
+            .ansi_parse()
+            .frames(),
+    );
+    assert_eq!(
+        frames,
+        vec!["\x1b[2Jone\x1b[H", "\x1b[2Jtwo", "\x1b[2Jthree"]
+    );
+}
+
+#[test]
+fn test_clear_screen_ignored_once_synchronized() {
+    let frames = frame_strings(
+        "\x1b[?2026h\x1b[2Jone\x1b[2Jtwo\x1b[?2026l"
+            .ansi_parse()
+            .frames(),
+    );
+    assert_eq!(frames.len(), 1);
+}
+
+#[test]
+fn test_time_gap_fallback() {
+    let items = vec![
+        (Duration::from_millis(0), Output::TextBlock("a")),
+        (Duration::from_millis(5), Output::TextBlock("b")),
+        (Duration::from_millis(200), Output::TextBlock("c")),
+        (Duration::from_millis(210), Output::TextBlock("d")),
+    ];
+
+    let frames: Vec<_> = FrameIterator::new(items.into_iter())
+        .with_gap(Duration::from_millis(50))
+        .collect();
+
+    assert_eq!(
+        frames,
+        vec![
+            Frame(vec![Output::TextBlock("a"), Output::TextBlock("b")]),
+            Frame(vec![Output::TextBlock("c"), Output::TextBlock("d")]),
+        ]
+    );
+}
+
+#[test]
+fn test_empty_stream() {
+    assert_eq!("".ansi_parse().frames().count(), 0);
+}
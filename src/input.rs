@@ -0,0 +1,822 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+#[cfg(test)]
+mod tests;
+
+use core::convert::TryInto;
+use core::ops::{BitOr, BitOrAssign};
+use nom::*;
+
+///Modifier keys held down while an input event was generated.
+///
+///The bit layout follows the kitty keyboard protocol, which is a superset of the xterm one
+///(`shift = 1`, `alt = 2`, `ctrl = 4`), so a modifier parameter can be converted by subtracting
+///one from it.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Modifiers(u8);
+
+impl Modifiers {
+    pub const SHIFT: Modifiers = Modifiers(1);
+    pub const ALT: Modifiers = Modifiers(1 << 1);
+    pub const CTRL: Modifiers = Modifiers(1 << 2);
+    pub const SUPER: Modifiers = Modifiers(1 << 3);
+    pub const HYPER: Modifiers = Modifiers(1 << 4);
+    pub const META: Modifiers = Modifiers(1 << 5);
+    pub const CAPS_LOCK: Modifiers = Modifiers(1 << 6);
+    pub const NUM_LOCK: Modifiers = Modifiers(1 << 7);
+
+    pub const fn empty() -> Self {
+        Modifiers(0)
+    }
+
+    pub const fn from_bits(bits: u8) -> Self {
+        Modifiers(bits)
+    }
+
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn contains(self, other: Modifiers) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn insert(&mut self, other: Modifiers) {
+        self.0 |= other.0;
+    }
+}
+
+impl BitOr for Modifiers {
+    type Output = Modifiers;
+
+    fn bitor(self, rhs: Modifiers) -> Modifiers {
+        Modifiers(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Modifiers {
+    fn bitor_assign(&mut self, rhs: Modifiers) {
+        self.0 |= rhs.0;
+    }
+}
+
+///The button reported by a mouse event.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    WheelUp,
+    WheelDown,
+    WheelLeft,
+    WheelRight,
+    ///Buttons 8 through 11, usually the back and forward side buttons.
+    Extra(u8),
+    ///The legacy encodings do not say which button was released, and plain motion events
+    ///have no button held down.
+    None,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MouseEventKind {
+    Press,
+    Release,
+    ///Motion with a button held down.
+    Drag,
+    ///Motion with no button held down, only reported in any-event tracking mode (`?1003`).
+    Move,
+    Scroll,
+}
+
+///A decoded mouse tracking report.
+///
+///`col` and `row` are 1-based, like [`AnsiSequence::CursorPos`](crate::AnsiSequence::CursorPos).
+///In SGR-pixels mode (`?1016`) they are pixel coordinates instead of cells.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct MouseEvent {
+    pub button: MouseButton,
+    pub modifiers: Modifiers,
+    pub kind: MouseEventKind,
+    pub col: u32,
+    pub row: u32,
+}
+
+///A control sequence split into its parts, without interpreting the parameters.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) struct Csi<'a> {
+    pub private: Option<char>,
+    pub params: &'a str,
+    pub intermediates: &'a str,
+    pub final_byte: char,
+}
+
+impl<'a> Csi<'a> {
+    ///Returns the `n`th `;` separated parameter, ignoring any `:` sub-parameters.
+    pub fn param(&self, n: usize) -> Option<u32> {
+        self.params
+            .split(';')
+            .nth(n)
+            .and_then(|p| p.split(':').next())
+            .and_then(|p| p.parse().ok())
+    }
+
+    ///Returns the `m`th `:` separated sub-parameter of the `n`th parameter.
+    pub fn sub_param(&self, n: usize, m: usize) -> Option<u32> {
+        self.params
+            .split(';')
+            .nth(n)?
+            .split(':')
+            .nth(m)?
+            .parse()
+            .ok()
+    }
+
+    pub fn param_count(&self) -> usize {
+        if self.params.is_empty() {
+            0
+        } else {
+            self.params.split(';').count()
+        }
+    }
+}
+
+fn is_csi_param(c: char) -> bool {
+    c.is_ascii_digit() || c == ';' || c == ':'
+}
+
+fn is_csi_intermediate(c: char) -> bool {
+    (' '..='/').contains(&c)
+}
+
+fn is_csi_final(c: char) -> bool {
+    ('@'..='~').contains(&c)
+}
+
+named!(
+    pub(crate) csi<&str, Csi<'_>>,
+    do_parse!(
+        tag!("[")                                 >>
+        private: opt!(one_of!("<=>?"))            >>
+        params: take_while!(is_csi_param)         >>
+        intermediates: take_while!(is_csi_intermediate) >>
+        final_byte: verify!(anychar, is_csi_final) >>
+        (Csi { private, params, intermediates, final_byte })
+    )
+);
+
+fn decode_mouse(cb: u32, col: u32, row: u32, release: bool) -> Option<MouseEvent> {
+    let mut modifiers = Modifiers::empty();
+    if cb & 4 != 0 {
+        modifiers |= Modifiers::SHIFT;
+    }
+    if cb & 8 != 0 {
+        modifiers |= Modifiers::ALT;
+    }
+    if cb & 16 != 0 {
+        modifiers |= Modifiers::CTRL;
+    }
+
+    let motion = cb & 32 != 0;
+    let low = cb & 3;
+    let button = match cb & 192 {
+        0 => match low {
+            0 => MouseButton::Left,
+            1 => MouseButton::Middle,
+            2 => MouseButton::Right,
+            _ => MouseButton::None,
+        },
+        64 => match low {
+            0 => MouseButton::WheelUp,
+            1 => MouseButton::WheelDown,
+            2 => MouseButton::WheelLeft,
+            _ => MouseButton::WheelRight,
+        },
+        128 => MouseButton::Extra(8 + low as u8),
+        _ => return None,
+    };
+
+    let kind = match button {
+        MouseButton::WheelUp
+        | MouseButton::WheelDown
+        | MouseButton::WheelLeft
+        | MouseButton::WheelRight => MouseEventKind::Scroll,
+        MouseButton::None if motion => MouseEventKind::Move,
+        MouseButton::None => MouseEventKind::Release,
+        _ if motion => MouseEventKind::Drag,
+        _ if release => MouseEventKind::Release,
+        _ => MouseEventKind::Press,
+    };
+
+    Some(MouseEvent {
+        button,
+        modifiers,
+        kind,
+        col,
+        row,
+    })
+}
+
+//X10 and normal tracking send each value as a single character offset by 32. In UTF-8 mode
+//(`?1005`) the characters may be multi-byte, which `anychar` already handles.
+named!(
+    mouse_x10_value<&str, u32>,
+    map_opt!(anychar, |c: char| (c as u32).checked_sub(32))
+);
+
+named!(
+    mouse_x10<&str, MouseEvent>,
+    do_parse!(
+        tag!("[M")               >>
+        cb: mouse_x10_value      >>
+        col: mouse_x10_value     >>
+        row: mouse_x10_value     >>
+        ev: expr_opt!(decode_mouse(cb, col, row, false)) >>
+        (ev)
+    )
+);
+
+//SGR (`?1006`) and SGR-pixels (`?1016`) reports share the same syntax, the final byte tells a
+//press from a release and the button code carries no offset.
+fn mouse_sgr_event(seq: Csi) -> Option<MouseEvent> {
+    if seq.private != Some('<') || seq.param_count() != 3 || !seq.intermediates.is_empty() {
+        return None;
+    }
+
+    let release = match seq.final_byte {
+        'M' => false,
+        'm' => true,
+        _ => return None,
+    };
+
+    decode_mouse(seq.param(0)?, seq.param(1)?, seq.param(2)?, release)
+}
+
+//urxvt (`?1015`) reports are decimal but keep the X10 offset on the button code.
+fn mouse_urxvt_event(seq: Csi) -> Option<MouseEvent> {
+    if seq.private.is_some()
+        || seq.final_byte != 'M'
+        || seq.param_count() != 3
+        || !seq.intermediates.is_empty()
+    {
+        return None;
+    }
+
+    decode_mouse(
+        seq.param(0)?.checked_sub(32)?,
+        seq.param(1)?,
+        seq.param(2)?,
+        false,
+    )
+}
+
+named!(
+    mouse_sgr<&str, MouseEvent>,
+    map_opt!(csi, mouse_sgr_event)
+);
+
+named!(
+    mouse_urxvt<&str, MouseEvent>,
+    map_opt!(csi, mouse_urxvt_event)
+);
+
+named!(
+    pub parse_mouse<&str, MouseEvent>,
+    do_parse!(
+        tag!("\u{1b}") >>
+        ev: alt!(
+              mouse_x10
+            | mouse_sgr
+            | mouse_urxvt
+        ) >>
+        (ev)
+    )
+);
+
+///A key, as far as the terminal encoding can tell them apart.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum KeyCode {
+    Char(char),
+    Enter,
+    Tab,
+    ///Shift+Tab, reported as `ESC[Z`.
+    BackTab,
+    Backspace,
+    Esc,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    Insert,
+    Delete,
+    PageUp,
+    PageDown,
+    ///The centre key of the keypad with num lock off.
+    Begin,
+    ///Function keys F1 through F35. xterm reports F21 and above with the non-standard codes
+    ///42 to 56, since the VT220 table stops at F20.
+    F(u8),
+    ///A keypad digit or operator in application keypad mode.
+    Keypad(char),
+    KeypadEnter,
+    CapsLock,
+    ScrollLock,
+    NumLock,
+    PrintScreen,
+    Pause,
+    Menu,
+    ///A kitty functional key without a dedicated variant, such as the media keys or the
+    ///modifier keys themselves, identified by its private use code point.
+    Other(u32),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct KeyEvent {
+    pub code: KeyCode,
+    pub modifiers: Modifiers,
+}
+
+impl KeyEvent {
+    pub const fn new(code: KeyCode, modifiers: Modifiers) -> Self {
+        KeyEvent { code, modifiers }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum KeyEventKind {
+    Press,
+    Repeat,
+    Release,
+}
+
+///A key report in the kitty keyboard protocol, `ESC[97;5u` is Ctrl+a.
+///
+///Which of the optional parts are filled in depends on the flags pushed with
+///[`AnsiSequence::PushKeyboardFlags`](crate::AnsiSequence::PushKeyboardFlags).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct KittyKeyEvent<'a> {
+    pub key: KeyEvent,
+    ///Always [`KeyEventKind::Press`] unless event types are reported (flag 2).
+    pub kind: KeyEventKind,
+    ///The key with shift applied, reported with the alternate keys flag (4).
+    pub shifted_key: Option<KeyCode>,
+    ///The key at the same position on a US PC-101 layout, reported with the alternate keys
+    ///flag (4).
+    pub base_layout_key: Option<KeyCode>,
+    text: &'a str,
+}
+
+impl<'a> KittyKeyEvent<'a> {
+    ///The text generated by the key, reported with the associated text flag (16).
+    pub fn text(&self) -> impl Iterator<Item = char> + 'a {
+        self.text
+            .split(':')
+            .filter_map(|c| c.parse().ok())
+            .filter_map(core::char::from_u32)
+    }
+}
+
+///Anything a terminal can send on its input side.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum InputEvent<'a> {
+    Key(KeyEvent),
+    KittyKey(KittyKeyEvent<'a>),
+    Mouse(MouseEvent),
+    ///The answer to [`AnsiSequence::QueryKeyboardFlags`](crate::AnsiSequence::QueryKeyboardFlags).
+    KeyboardFlags(u8),
+    ///The answer to
+    ///[`AnsiSequence::QueryKeyModifierOption`](crate::AnsiSequence::QueryKeyModifierOption),
+    ///the resource followed by its value.
+    KeyModifierOption(u8, u8),
+    ///The terminal window gained focus, reported when
+    ///[`AnsiSequence::SetFocusReporting`](crate::AnsiSequence::SetFocusReporting) is on.
+    FocusIn,
+    FocusOut,
+    ///Text pasted in bracketed paste mode, escape sequences inside it are not interpreted.
+    ///Only produced by [`InputDecoder`].
+    Paste(&'a str),
+    ///The start of a paste that continues in the next chunk of input. The pieces up to and
+    ///including the next [`InputEvent::Paste`] make up the pasted text.
+    PartialPaste(&'a str),
+}
+
+//xterm sends modifiers as `1 + bits`. The fourth bit is called Meta by xterm, it lands on the
+//key that kitty calls Super.
+fn xterm_modifiers(param: Option<u32>) -> Modifiers {
+    match param {
+        Some(p) if p > 1 && p <= 256 => Modifiers::from_bits((p - 1) as u8),
+        _ => Modifiers::empty(),
+    }
+}
+
+fn letter_key(c: char) -> Option<KeyCode> {
+    use KeyCode::*;
+    Some(match c {
+        'A' => Up,
+        'B' => Down,
+        'C' => Right,
+        'D' => Left,
+        'E' => Begin,
+        'F' => End,
+        'H' => Home,
+        'P' => F(1),
+        'Q' => F(2),
+        'R' => F(3),
+        'S' => F(4),
+        _ => return None,
+    })
+}
+
+//The VT220 numbering skips 16, 22, 27 and 30, rxvt adds 7 and 8 for Home and End.
+fn tilde_key(code: u32) -> Option<KeyCode> {
+    use KeyCode::*;
+    Some(match code {
+        1 | 7 => Home,
+        2 => Insert,
+        3 => Delete,
+        4 | 8 => End,
+        5 => PageUp,
+        6 => PageDown,
+        11..=15 => F((code - 10) as u8),
+        17..=21 => F((code - 11) as u8),
+        23..=26 => F((code - 12) as u8),
+        28 | 29 => F((code - 13) as u8),
+        31..=34 => F((code - 14) as u8),
+        42..=56 => F((code - 21) as u8),
+        _ => return None,
+    })
+}
+
+fn keypad_key(c: char) -> Option<KeyCode> {
+    use KeyCode::*;
+    Some(match c {
+        'p'..='y' => Keypad((b'0' + (c as u8 - b'p')) as char),
+        'j' => Keypad('*'),
+        'k' => Keypad('+'),
+        'l' => Keypad(','),
+        'm' => Keypad('-'),
+        'n' => Keypad('.'),
+        'o' => Keypad('/'),
+        'X' => Keypad('='),
+        ' ' => Keypad(' '),
+        'I' => Tab,
+        'M' => KeypadEnter,
+        _ => return None,
+    })
+}
+
+fn plain_key(c: char) -> KeyEvent {
+    use KeyCode::*;
+    match c {
+        '\r' | '\n' => KeyEvent::new(Enter, Modifiers::empty()),
+        '\t' => KeyEvent::new(Tab, Modifiers::empty()),
+        '\u{8}' | '\u{7f}' => KeyEvent::new(Backspace, Modifiers::empty()),
+        '\u{1b}' => KeyEvent::new(Esc, Modifiers::empty()),
+        '\0' => KeyEvent::new(Char(' '), Modifiers::CTRL),
+        '\u{1}'..='\u{1a}' => KeyEvent::new(Char((c as u8 - 1 + b'a') as char), Modifiers::CTRL),
+        '\u{1c}'..='\u{1f}' => KeyEvent::new(Char((c as u8 + 0x40) as char), Modifiers::CTRL),
+        _ => KeyEvent::new(Char(c), Modifiers::empty()),
+    }
+}
+
+fn csi_key(seq: Csi) -> Option<KeyEvent> {
+    if seq.private.is_some() || !seq.intermediates.is_empty() {
+        return None;
+    }
+
+    match seq.final_byte {
+        //modifyOtherKeys reports `CSI 27 ; modifiers ; code ~` for keys that would otherwise
+        //lose their modifiers, such as Ctrl+Enter.
+        '~' if seq.param(0) == Some(27) => {
+            if seq.param_count() != 3 {
+                return None;
+            }
+            Some(KeyEvent::new(
+                kitty_key_code(seq.param(2)?)?,
+                xterm_modifiers(seq.param(1)),
+            ))
+        }
+        '~' => Some(KeyEvent::new(
+            tilde_key(seq.param(0)?)?,
+            xterm_modifiers(seq.param(1)),
+        )),
+        'Z' => Some(KeyEvent::new(
+            KeyCode::BackTab,
+            Modifiers::SHIFT | xterm_modifiers(seq.param(1)),
+        )),
+        c => {
+            //Only `ESC[A` and `ESC[1;mA` are keys, `ESC[5A` is a cursor movement.
+            if seq.param_count() > 2 || seq.param(0).unwrap_or(1) != 1 {
+                return None;
+            }
+            Some(KeyEvent::new(letter_key(c)?, xterm_modifiers(seq.param(1))))
+        }
+    }
+}
+
+fn ss3_key(modifier: Option<char>, c: char) -> Option<KeyEvent> {
+    let modifiers = xterm_modifiers(modifier.and_then(|m| m.to_digit(10)));
+    letter_key(c)
+        .or_else(|| keypad_key(c))
+        .map(|code| KeyEvent::new(code, modifiers))
+}
+
+//Application cursor and keypad mode, `ESC O A` is the up arrow. Some terminals put an xterm
+//modifier digit between the `O` and the final character. In output `ESC O` is a single shift
+//instead, see `AnsiSequence::SetSingleShift3`.
+named!(
+    key_ss3<&str, KeyEvent>,
+    do_parse!(
+        tag!("O")                           >>
+        modifier: opt!(one_of!("2345678"))  >>
+        key: anychar                        >>
+        ev: expr_opt!(ss3_key(modifier, key)) >>
+        (ev)
+    )
+);
+
+//The Linux console sends F1 to F5 as `ESC[[A` to `ESC[[E`.
+named!(
+    key_linux_fn<&str, KeyEvent>,
+    do_parse!(
+        tag!("[[")          >>
+        key: one_of!("ABCDE") >>
+        (KeyEvent::new(KeyCode::F(key as u8 - b'A' + 1), Modifiers::empty()))
+    )
+);
+
+named!(
+    key_csi<&str, KeyEvent>,
+    map_opt!(csi, csi_key)
+);
+
+named!(
+    key_sequence<&str, KeyEvent>,
+    alt!(
+          key_ss3
+        | key_linux_fn
+        | key_csi
+    )
+);
+
+fn kitty_key_code(code: u32) -> Option<KeyCode> {
+    use KeyCode::*;
+    Some(match code {
+        9 => Tab,
+        13 => Enter,
+        27 => Esc,
+        127 => Backspace,
+        57358 => CapsLock,
+        57359 => ScrollLock,
+        57360 => NumLock,
+        57361 => PrintScreen,
+        57362 => Pause,
+        57363 => Menu,
+        57376..=57398 => F((code - 57376 + 13) as u8),
+        57399..=57408 => Keypad((b'0' + (code - 57399) as u8) as char),
+        57409 => Keypad('.'),
+        57410 => Keypad('/'),
+        57411 => Keypad('*'),
+        57412 => Keypad('-'),
+        57413 => Keypad('+'),
+        57414 => KeypadEnter,
+        57415 => Keypad('='),
+        57416 => Keypad(','),
+        57417 => Left,
+        57418 => Right,
+        57419 => Up,
+        57420 => Down,
+        57421 => PageUp,
+        57422 => PageDown,
+        57423 => Home,
+        57424 => End,
+        57425 => Insert,
+        57426 => Delete,
+        57427 => Begin,
+        57428..=57454 => Other(code),
+        _ => Char(core::char::from_u32(code)?),
+    })
+}
+
+//Besides `CSI ... u`, kitty keeps the legacy `~` and letter forms for functional keys, but they
+//can only be told apart from the legacy reports when an event type sub-parameter is present.
+fn kitty_event(seq: Csi) -> Option<KittyKeyEvent> {
+    if seq.private.is_some() || !seq.intermediates.is_empty() || seq.param_count() > 3 {
+        return None;
+    }
+
+    let code = match seq.final_byte {
+        'u' => kitty_key_code(seq.param(0)?)?,
+        '~' if seq.params.contains(':') => {
+            let code = seq.param(0)?;
+            tilde_key(code).or_else(|| kitty_key_code(code))?
+        }
+        c if seq.params.contains(':') && seq.param(0).unwrap_or(1) == 1 => letter_key(c)?,
+        _ => return None,
+    };
+
+    let kind = match seq.sub_param(1, 1) {
+        None | Some(1) => KeyEventKind::Press,
+        Some(2) => KeyEventKind::Repeat,
+        Some(3) => KeyEventKind::Release,
+        _ => return None,
+    };
+
+    let alternate = |m| match seq.final_byte {
+        'u' => seq.sub_param(0, m).and_then(kitty_key_code),
+        _ => None,
+    };
+
+    Some(KittyKeyEvent {
+        key: KeyEvent::new(code, xterm_modifiers(seq.sub_param(1, 0))),
+        kind,
+        shifted_key: alternate(1),
+        base_layout_key: alternate(2),
+        text: seq.params.split(';').nth(2).unwrap_or(""),
+    })
+}
+
+fn keyboard_flags(seq: Csi) -> Option<u8> {
+    if seq.private != Some('?') || seq.final_byte != 'u' || !seq.intermediates.is_empty() {
+        return None;
+    }
+    seq.param(0)?.try_into().ok()
+}
+
+fn focus_event(seq: Csi) -> Option<InputEvent<'static>> {
+    if seq.private.is_some() || !seq.params.is_empty() || !seq.intermediates.is_empty() {
+        return None;
+    }
+    match seq.final_byte {
+        'I' => Some(InputEvent::FocusIn),
+        'O' => Some(InputEvent::FocusOut),
+        _ => None,
+    }
+}
+
+fn key_modifier_option(seq: Csi) -> Option<(u8, u8)> {
+    if seq.private != Some('>')
+        || seq.final_byte != 'm'
+        || seq.param_count() != 2
+        || !seq.intermediates.is_empty()
+    {
+        return None;
+    }
+    Some((
+        seq.param(0)?.try_into().ok()?,
+        seq.param(1)?.try_into().ok()?,
+    ))
+}
+
+named!(
+    pub parse_kitty_key<&str, KittyKeyEvent<'_>>,
+    do_parse!(
+        tag!("\u{1b}")                >>
+        ev: map_opt!(csi, kitty_event) >>
+        (ev)
+    )
+);
+
+named!(
+    input_report<&str, InputEvent<'_>>,
+    do_parse!(
+        peek!(tag!("\u{1b}[")) >>
+        ev: alt!(
+              map!(parse_mouse, InputEvent::Mouse)
+            | map!(parse_kitty_key, InputEvent::KittyKey)
+            | map!(
+                preceded!(tag!("\u{1b}"), map_opt!(csi, keyboard_flags)),
+                InputEvent::KeyboardFlags
+            )
+            | preceded!(tag!("\u{1b}"), map_opt!(csi, focus_event))
+            | map!(
+                preceded!(tag!("\u{1b}"), map_opt!(csi, key_modifier_option)),
+                |(resource, value)| InputEvent::KeyModifierOption(resource, value)
+            )
+        ) >>
+        (ev)
+    )
+);
+
+fn alt_key(input: &str) -> IResult<&str, KeyEvent> {
+    let (rest, c) = anychar(input)?;
+    let mut key = plain_key(c);
+    key.modifiers |= Modifiers::ALT;
+    Ok((rest, key))
+}
+
+///Decodes a single key press from the start of `input`.
+///
+///Pass `more_pending` as `true` when the caller knows that more bytes are still on their way.
+///Then a lone `ESC`, or an escape sequence cut short, returns [`nom::Err::Incomplete`]. When it
+///is `false` the bytes are final, a lone `ESC` is the escape key and an `ESC` that does not
+///start a complete sequence is the Alt prefix of the key after it.
+pub fn parse_key(input: &str, more_pending: bool) -> IResult<&str, KeyEvent> {
+    let (rest, c) = anychar(input)?;
+    if c != '\u{1b}' {
+        return Ok((rest, plain_key(c)));
+    }
+
+    match rest.chars().next() {
+        None if more_pending => Err(Err::Incomplete(Needed::Size(1))),
+        None => Ok((rest, plain_key(c))),
+        Some('[') | Some('O') => match key_sequence(rest) {
+            Err(Err::Incomplete(_)) if !more_pending => alt_key(rest),
+            //A well formed control sequence that is not a key is an error, anything else was
+            //typed with Alt held down.
+            Err(Err::Error(e)) if rest.starts_with('[') && csi(rest).is_ok() => Err(Err::Error(e)),
+            Err(Err::Error(_)) => alt_key(rest),
+            res => res,
+        },
+        Some('\u{1b}') => {
+            let (rest, mut key) = parse_key(rest, more_pending)?;
+            key.modifiers |= Modifiers::ALT;
+            Ok((rest, key))
+        }
+        Some(_) => alt_key(rest),
+    }
+}
+
+///Decodes a single key press or report from the start of `input`. Kitty key reports take
+///precedence over the legacy encodings they extend. See [`parse_key`] for
+///the meaning of `more_pending`.
+pub fn parse_input(input: &str, more_pending: bool) -> IResult<&str, InputEvent<'_>> {
+    match input_report(input) {
+        Ok(res) => return Ok(res),
+        Err(Err::Incomplete(n)) if more_pending => return Err(Err::Incomplete(n)),
+        _ => {}
+    }
+
+    let (rest, key) = parse_key(input, more_pending)?;
+    Ok((rest, InputEvent::Key(key)))
+}
+
+const PASTE_START: &str = "\u{1b}[200~";
+const PASTE_END: &str = "\u{1b}[201~";
+
+///A stateful wrapper around [`parse_input`] for decoding a stream that arrives in chunks.
+///
+///It keeps track of bracketed pastes, so the text between
+///[`AnsiSequence::PasteStart`](crate::AnsiSequence::PasteStart) and
+///[`AnsiSequence::PasteEnd`](crate::AnsiSequence::PasteEnd) is never decoded as keys, even when
+///the paste is split across chunks.
+#[derive(Debug, Default, Clone)]
+pub struct InputDecoder {
+    in_paste: bool,
+}
+
+impl InputDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///Whether the last event was a [`InputEvent::PartialPaste`] whose paste has not ended yet.
+    pub fn in_paste(&self) -> bool {
+        self.in_paste
+    }
+
+    ///Decodes the next event from the start of `input`. On [`nom::Err::Incomplete`] the input
+    ///is left untouched and should be passed in again with more bytes appended. See
+    ///[`parse_key`] for the meaning of `more_pending`.
+    pub fn decode<'a>(
+        &mut self,
+        input: &'a str,
+        more_pending: bool,
+    ) -> IResult<&'a str, InputEvent<'a>> {
+        let text = if self.in_paste {
+            input
+        } else if let Some(text) = input.strip_prefix(PASTE_START) {
+            text
+        } else {
+            return parse_input(input, more_pending);
+        };
+
+        if let Some(end) = text.find(PASTE_END) {
+            self.in_paste = false;
+            return Ok((
+                &text[end + PASTE_END.len()..],
+                InputEvent::Paste(&text[..end]),
+            ));
+        }
+
+        //Hold back the start of an end marker that was cut off at the end of the chunk.
+        let held = (1..PASTE_END.len())
+            .rev()
+            .find(|&n| text.ends_with(&PASTE_END[..n]))
+            .unwrap_or(0);
+        let split = text.len() - held;
+        if split == 0 {
+            return Err(Err::Incomplete(Needed::Size(PASTE_END.len() - held)));
+        }
+
+        self.in_paste = true;
+        Ok((&text[split..], InputEvent::PartialPaste(&text[..split])))
+    }
+}
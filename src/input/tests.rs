@@ -0,0 +1,511 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+use super::*;
+
+fn mouse(input: &str) -> MouseEvent {
+    let (rest, ev) = parse_mouse(input).expect("failed to parse mouse report");
+    assert_eq!(rest, "");
+    ev
+}
+
+#[test]
+fn test_mouse_x10_press() {
+    let ev = mouse("\u{1b}[M !!");
+    assert_eq!(
+        ev,
+        MouseEvent {
+            button: MouseButton::Left,
+            modifiers: Modifiers::empty(),
+            kind: MouseEventKind::Press,
+            col: 1,
+            row: 1,
+        }
+    );
+}
+
+#[test]
+fn test_mouse_x10_release_and_modifiers() {
+    // 3 + 32 = release, 4 + 16 = shift + ctrl.
+    let ev = mouse("\u{1b}[M7*+");
+    assert_eq!(ev.button, MouseButton::None);
+    assert_eq!(ev.kind, MouseEventKind::Release);
+    assert_eq!(ev.modifiers, Modifiers::SHIFT | Modifiers::CTRL);
+    assert_eq!((ev.col, ev.row), (10, 11));
+}
+
+#[test]
+fn test_mouse_x10_drag_and_move() {
+    let drag = mouse("\u{1b}[M@!!");
+    assert_eq!(drag.button, MouseButton::Left);
+    assert_eq!(drag.kind, MouseEventKind::Drag);
+
+    let moved = mouse("\u{1b}[MC!!");
+    assert_eq!(moved.button, MouseButton::None);
+    assert_eq!(moved.kind, MouseEventKind::Move);
+}
+
+#[test]
+fn test_mouse_x10_wheel() {
+    let up = mouse("\u{1b}[M`!!");
+    assert_eq!(up.button, MouseButton::WheelUp);
+    assert_eq!(up.kind, MouseEventKind::Scroll);
+
+    let down = mouse("\u{1b}[Ma!!");
+    assert_eq!(down.button, MouseButton::WheelDown);
+}
+
+#[test]
+fn test_mouse_x10_utf8_coordinates() {
+    // Column 300 only fits in UTF-8 extended mode.
+    let ev = mouse("\u{1b}[M \u{14c}!");
+    assert_eq!((ev.col, ev.row), (300, 1));
+}
+
+#[test]
+fn test_mouse_sgr() {
+    let press = mouse("\u{1b}[<0;120;45M");
+    assert_eq!(press.button, MouseButton::Left);
+    assert_eq!(press.kind, MouseEventKind::Press);
+    assert_eq!((press.col, press.row), (120, 45));
+
+    let release = mouse("\u{1b}[<2;120;45m");
+    assert_eq!(release.button, MouseButton::Right);
+    assert_eq!(release.kind, MouseEventKind::Release);
+
+    let drag = mouse("\u{1b}[<33;3;4M");
+    assert_eq!(drag.button, MouseButton::Middle);
+    assert_eq!(drag.kind, MouseEventKind::Drag);
+
+    let side = mouse("\u{1b}[<129;1;1M");
+    assert_eq!(side.button, MouseButton::Extra(9));
+}
+
+#[test]
+fn test_mouse_sgr_pixels() {
+    let ev = mouse("\u{1b}[<65;1024;768M");
+    assert_eq!(ev.button, MouseButton::WheelDown);
+    assert_eq!((ev.col, ev.row), (1024, 768));
+}
+
+#[test]
+fn test_mouse_urxvt() {
+    let ev = mouse("\u{1b}[40;5;6M");
+    assert_eq!(ev.button, MouseButton::Left);
+    assert_eq!(ev.modifiers, Modifiers::ALT);
+    assert_eq!(ev.kind, MouseEventKind::Press);
+    assert_eq!((ev.col, ev.row), (5, 6));
+}
+
+#[test]
+fn test_mouse_incomplete_and_invalid() {
+    assert!(matches!(
+        parse_mouse("\u{1b}[<0;12"),
+        Err(nom::Err::Incomplete(_))
+    ));
+    assert!(matches!(
+        parse_mouse("\u{1b}[M "),
+        Err(nom::Err::Incomplete(_))
+    ));
+    assert!(parse_mouse("\u{1b}[5A").is_err());
+    assert!(parse_mouse("\u{1b}[<0;1M").is_err());
+}
+
+fn key(input: &str) -> KeyEvent {
+    let (rest, ev) = parse_key(input, false).expect("failed to parse key");
+    assert_eq!(rest, "");
+    ev
+}
+
+macro_rules! test_key {
+    ($name:ident, $string:expr, $code:expr) => {
+        test_key!($name, $string, $code, Modifiers::empty());
+    };
+    ($name:ident, $string:expr, $code:expr, $mods:expr) => {
+        #[test]
+        fn $name() {
+            assert_eq!(key($string), KeyEvent::new($code, $mods));
+        }
+    };
+}
+
+test_key!(key_char, "a", KeyCode::Char('a'));
+test_key!(key_unicode_char, "é", KeyCode::Char('é'));
+test_key!(key_enter, "\r", KeyCode::Enter);
+test_key!(key_tab, "\t", KeyCode::Tab);
+test_key!(key_backspace, "\u{7f}", KeyCode::Backspace);
+test_key!(key_ctrl_a, "\u{1}", KeyCode::Char('a'), Modifiers::CTRL);
+test_key!(key_ctrl_space, "\0", KeyCode::Char(' '), Modifiers::CTRL);
+test_key!(
+    key_ctrl_backslash,
+    "\u{1c}",
+    KeyCode::Char('\\'),
+    Modifiers::CTRL
+);
+
+test_key!(key_up, "\u{1b}[A", KeyCode::Up);
+test_key!(key_ss3_up, "\u{1b}OA", KeyCode::Up);
+test_key!(key_ctrl_up, "\u{1b}[1;5A", KeyCode::Up, Modifiers::CTRL);
+test_key!(
+    key_ctrl_shift_right,
+    "\u{1b}[1;6C",
+    KeyCode::Right,
+    Modifiers::CTRL | Modifiers::SHIFT
+);
+test_key!(key_home, "\u{1b}[H", KeyCode::Home);
+test_key!(key_end_ss3, "\u{1b}OF", KeyCode::End);
+test_key!(key_begin, "\u{1b}[E", KeyCode::Begin);
+test_key!(key_back_tab, "\u{1b}[Z", KeyCode::BackTab, Modifiers::SHIFT);
+
+test_key!(key_insert, "\u{1b}[2~", KeyCode::Insert);
+test_key!(key_delete, "\u{1b}[3~", KeyCode::Delete);
+test_key!(key_page_up, "\u{1b}[5~", KeyCode::PageUp);
+test_key!(
+    key_page_down_alt,
+    "\u{1b}[6;3~",
+    KeyCode::PageDown,
+    Modifiers::ALT
+);
+test_key!(key_rxvt_home, "\u{1b}[7~", KeyCode::Home);
+
+test_key!(key_f1_ss3, "\u{1b}OP", KeyCode::F(1));
+test_key!(key_f4_shift, "\u{1b}[1;2S", KeyCode::F(4), Modifiers::SHIFT);
+test_key!(
+    key_f1_ss3_modifier,
+    "\u{1b}O5P",
+    KeyCode::F(1),
+    Modifiers::CTRL
+);
+test_key!(key_f5, "\u{1b}[15~", KeyCode::F(5));
+test_key!(key_f6, "\u{1b}[17~", KeyCode::F(6));
+test_key!(key_f11, "\u{1b}[23~", KeyCode::F(11));
+test_key!(key_f15, "\u{1b}[28~", KeyCode::F(15));
+test_key!(key_f20, "\u{1b}[34~", KeyCode::F(20));
+test_key!(key_f24, "\u{1b}[45~", KeyCode::F(24));
+test_key!(key_linux_f3, "\u{1b}[[C", KeyCode::F(3));
+
+test_key!(key_keypad_7, "\u{1b}Ow", KeyCode::Keypad('7'));
+test_key!(key_keypad_minus, "\u{1b}Om", KeyCode::Keypad('-'));
+test_key!(key_keypad_enter, "\u{1b}OM", KeyCode::KeypadEnter);
+
+test_key!(key_lone_escape, "\u{1b}", KeyCode::Esc);
+test_key!(key_alt_x, "\u{1b}x", KeyCode::Char('x'), Modifiers::ALT);
+test_key!(
+    key_alt_ctrl_c,
+    "\u{1b}\u{3}",
+    KeyCode::Char('c'),
+    Modifiers::ALT | Modifiers::CTRL
+);
+test_key!(key_alt_escape, "\u{1b}\u{1b}", KeyCode::Esc, Modifiers::ALT);
+test_key!(key_alt_up, "\u{1b}\u{1b}[A", KeyCode::Up, Modifiers::ALT);
+test_key!(
+    key_alt_bracket,
+    "\u{1b}[",
+    KeyCode::Char('['),
+    Modifiers::ALT
+);
+test_key!(
+    key_alt_shift_o,
+    "\u{1b}O",
+    KeyCode::Char('O'),
+    Modifiers::ALT
+);
+
+#[test]
+fn test_key_pending_escape() {
+    assert!(matches!(
+        parse_key("\u{1b}", true),
+        Err(nom::Err::Incomplete(_))
+    ));
+    assert!(matches!(
+        parse_key("\u{1b}[1;5", true),
+        Err(nom::Err::Incomplete(_))
+    ));
+    assert!(matches!(
+        parse_key("\u{1b}O", true),
+        Err(nom::Err::Incomplete(_))
+    ));
+}
+
+#[test]
+fn test_key_leaves_rest() {
+    let (rest, ev) = parse_key("\u{1b}[Bjk", true).unwrap();
+    assert_eq!(ev, KeyEvent::new(KeyCode::Down, Modifiers::empty()));
+    assert_eq!(rest, "jk");
+
+    let (rest, ev) = parse_key("\u{1b}OAOA", false).unwrap();
+    assert_eq!(ev.code, KeyCode::Up);
+    assert_eq!(rest, "OA");
+}
+
+#[test]
+fn test_key_unknown_sequence() {
+    assert!(parse_key("\u{1b}[5A", false).is_err());
+    assert!(parse_key("\u{1b}[99~", false).is_err());
+}
+
+#[test]
+fn test_input_mixes_keys_and_mouse() {
+    let mut input = "\u{1b}[<0;3;4Mq\u{1b}[1;5D";
+    let mut events = Vec::new();
+    while !input.is_empty() {
+        let (rest, ev) = parse_input(input, false).unwrap();
+        events.push(ev);
+        input = rest;
+    }
+
+    assert_eq!(events.len(), 3);
+    assert!(matches!(events[0], InputEvent::Mouse(_)));
+    assert_eq!(
+        events[1],
+        InputEvent::Key(KeyEvent::new(KeyCode::Char('q'), Modifiers::empty()))
+    );
+    assert_eq!(
+        events[2],
+        InputEvent::Key(KeyEvent::new(KeyCode::Left, Modifiers::CTRL))
+    );
+}
+
+fn kitty(input: &str) -> KittyKeyEvent<'_> {
+    let (rest, ev) = parse_kitty_key(input).expect("failed to parse kitty key");
+    assert_eq!(rest, "");
+    ev
+}
+
+#[test]
+fn test_kitty_plain_and_modified() {
+    let ev = kitty("\u{1b}[97u");
+    assert_eq!(
+        ev.key,
+        KeyEvent::new(KeyCode::Char('a'), Modifiers::empty())
+    );
+    assert_eq!(ev.kind, KeyEventKind::Press);
+
+    let ev = kitty("\u{1b}[97;5u");
+    assert_eq!(ev.key, KeyEvent::new(KeyCode::Char('a'), Modifiers::CTRL));
+
+    let ev = kitty("\u{1b}[13;65u");
+    assert_eq!(ev.key.code, KeyCode::Enter);
+    assert_eq!(ev.key.modifiers, Modifiers::CAPS_LOCK);
+}
+
+#[test]
+fn test_kitty_event_types() {
+    assert_eq!(kitty("\u{1b}[97;1:2u").kind, KeyEventKind::Repeat);
+    assert_eq!(kitty("\u{1b}[97;1:3u").kind, KeyEventKind::Release);
+
+    let ev = kitty("\u{1b}[1;5:3A");
+    assert_eq!(ev.key, KeyEvent::new(KeyCode::Up, Modifiers::CTRL));
+    assert_eq!(ev.kind, KeyEventKind::Release);
+
+    let ev = kitty("\u{1b}[3;1:2~");
+    assert_eq!(ev.key.code, KeyCode::Delete);
+    assert_eq!(ev.kind, KeyEventKind::Repeat);
+
+    assert!(parse_kitty_key("\u{1b}[97;1:4u").is_err());
+}
+
+#[test]
+fn test_kitty_alternate_keys_and_text() {
+    let ev = kitty("\u{1b}[97:65:97;2;65u");
+    assert_eq!(ev.key, KeyEvent::new(KeyCode::Char('a'), Modifiers::SHIFT));
+    assert_eq!(ev.shifted_key, Some(KeyCode::Char('A')));
+    assert_eq!(ev.base_layout_key, Some(KeyCode::Char('a')));
+    assert_eq!(ev.text().collect::<String>(), "A");
+
+    let ev = kitty("\u{1b}[1089::99;;1089u");
+    assert_eq!(ev.key.code, KeyCode::Char('с'));
+    assert_eq!(ev.shifted_key, None);
+    assert_eq!(ev.base_layout_key, Some(KeyCode::Char('c')));
+    assert_eq!(ev.text().collect::<String>(), "с");
+}
+
+#[test]
+fn test_kitty_functional_keys() {
+    assert_eq!(kitty("\u{1b}[57376u").key.code, KeyCode::F(13));
+    assert_eq!(kitty("\u{1b}[57387u").key.code, KeyCode::F(24));
+    assert_eq!(kitty("\u{1b}[57399u").key.code, KeyCode::Keypad('0'));
+    assert_eq!(kitty("\u{1b}[57414u").key.code, KeyCode::KeypadEnter);
+    assert_eq!(kitty("\u{1b}[57441u").key.code, KeyCode::Other(57441));
+    assert_eq!(kitty("\u{1b}[27u").key.code, KeyCode::Esc);
+}
+
+#[test]
+fn test_kitty_does_not_steal_legacy_keys() {
+    assert!(parse_kitty_key("\u{1b}[A").is_err());
+    assert!(parse_kitty_key("\u{1b}[1;5A").is_err());
+    assert!(parse_kitty_key("\u{1b}[?1u").is_err());
+}
+
+#[test]
+fn test_input_kitty_reports() {
+    let (_, ev) = parse_input("\u{1b}[99;5u", false).unwrap();
+    assert!(matches!(ev, InputEvent::KittyKey(k) if k.key.code == KeyCode::Char('c')));
+
+    let (_, ev) = parse_input("\u{1b}[1;5A", false).unwrap();
+    assert_eq!(
+        ev,
+        InputEvent::Key(KeyEvent::new(KeyCode::Up, Modifiers::CTRL))
+    );
+
+    let (_, ev) = parse_input("\u{1b}[?15u", false).unwrap();
+    assert_eq!(ev, InputEvent::KeyboardFlags(15));
+
+    assert!(matches!(
+        parse_input("\u{1b}[97;", true),
+        Err(nom::Err::Incomplete(_))
+    ));
+}
+
+test_key!(
+    key_modify_other_keys_ctrl_enter,
+    "\u{1b}[27;5;13~",
+    KeyCode::Enter,
+    Modifiers::CTRL
+);
+test_key!(
+    key_modify_other_keys_ctrl_shift_a,
+    "\u{1b}[27;6;65~",
+    KeyCode::Char('A'),
+    Modifiers::CTRL | Modifiers::SHIFT
+);
+
+#[test]
+fn test_modify_other_keys_reports() {
+    assert!(parse_key("\u{1b}[27;5~", false).is_err());
+
+    let (_, ev) = parse_input("\u{1b}[>4;2m", false).unwrap();
+    assert_eq!(ev, InputEvent::KeyModifierOption(4, 2));
+
+    let (_, ev) = parse_input("\u{1b}[27;3;9~", false).unwrap();
+    assert_eq!(
+        ev,
+        InputEvent::Key(KeyEvent::new(KeyCode::Tab, Modifiers::ALT))
+    );
+}
+
+fn decode_all<'a>(
+    decoder: &mut InputDecoder,
+    mut input: &'a str,
+) -> (Vec<InputEvent<'a>>, &'a str) {
+    let mut events = Vec::new();
+    while !input.is_empty() {
+        match decoder.decode(input, true) {
+            Ok((rest, ev)) => {
+                events.push(ev);
+                input = rest;
+            }
+            Err(nom::Err::Incomplete(_)) => break,
+            Err(e) => panic!("failed to decode input: {:?}", e),
+        }
+    }
+    (events, input)
+}
+
+#[test]
+fn test_paste_is_opaque() {
+    let mut decoder = InputDecoder::new();
+    let (events, rest) = decode_all(
+        &mut decoder,
+        "a\u{1b}[200~rm -rf\u{1b}[2J\u{1b}[A\r\u{1b}[201~b",
+    );
+
+    assert_eq!(rest, "");
+    assert_eq!(
+        events,
+        vec![
+            InputEvent::Key(KeyEvent::new(KeyCode::Char('a'), Modifiers::empty())),
+            InputEvent::Paste("rm -rf\u{1b}[2J\u{1b}[A\r"),
+            InputEvent::Key(KeyEvent::new(KeyCode::Char('b'), Modifiers::empty())),
+        ]
+    );
+    assert!(!decoder.in_paste());
+}
+
+#[test]
+fn test_paste_empty() {
+    let mut decoder = InputDecoder::new();
+    let (events, _) = decode_all(&mut decoder, "\u{1b}[200~\u{1b}[201~");
+    assert_eq!(events, vec![InputEvent::Paste("")]);
+}
+
+#[test]
+fn test_paste_split_across_chunks() {
+    let mut decoder = InputDecoder::new();
+
+    let (events, rest) = decode_all(&mut decoder, "\u{1b}[200~hello ");
+    assert_eq!(events, vec![InputEvent::PartialPaste("hello ")]);
+    assert_eq!(rest, "");
+    assert!(decoder.in_paste());
+
+    let (events, rest) = decode_all(&mut decoder, "\u{1b}[Aworld\u{1b}[201~\u{1b}[B");
+    assert_eq!(rest, "");
+    assert_eq!(
+        events,
+        vec![
+            InputEvent::Paste("\u{1b}[Aworld"),
+            InputEvent::Key(KeyEvent::new(KeyCode::Down, Modifiers::empty())),
+        ]
+    );
+}
+
+#[test]
+fn test_paste_held_back_marker_was_text() {
+    let mut decoder = InputDecoder::new();
+
+    let (events, rest) = decode_all(&mut decoder, "\u{1b}[200~a\u{1b}[20");
+    assert_eq!(events, vec![InputEvent::PartialPaste("a")]);
+    assert_eq!(rest, "\u{1b}[20");
+
+    let chunk = format!("{}9~b\u{1b}[201~", rest);
+    let (events, _) = decode_all(&mut decoder, &chunk);
+    assert_eq!(events, vec![InputEvent::Paste("\u{1b}[209~b")]);
+}
+
+#[test]
+fn test_paste_split_end_marker() {
+    let mut decoder = InputDecoder::new();
+
+    let (events, rest) = decode_all(&mut decoder, "\u{1b}[200~abc\u{1b}[2");
+    assert_eq!(events, vec![InputEvent::PartialPaste("abc")]);
+
+    let chunk = format!("{}01~", rest);
+    let (events, rest) = decode_all(&mut decoder, &chunk);
+    assert_eq!(events, vec![InputEvent::Paste("")]);
+    assert_eq!(rest, "");
+    assert!(!decoder.in_paste());
+}
+
+#[test]
+fn test_paste_start_incomplete() {
+    let mut decoder = InputDecoder::new();
+    assert!(matches!(
+        decoder.decode("\u{1b}[200~", true),
+        Err(nom::Err::Incomplete(_))
+    ));
+    assert!(!decoder.in_paste());
+}
+
+#[test]
+fn test_focus_events() {
+    let (rest, ev) = parse_input("\u{1b}[O\u{1b}OA", false).unwrap();
+    assert_eq!(ev, InputEvent::FocusOut);
+
+    let (rest, ev) = parse_input(rest, false).unwrap();
+    assert_eq!(
+        ev,
+        InputEvent::Key(KeyEvent::new(KeyCode::Up, Modifiers::empty()))
+    );
+    assert_eq!(rest, "");
+
+    let (_, ev) = parse_input("\u{1b}[I", false).unwrap();
+    assert_eq!(ev, InputEvent::FocusIn);
+
+    // A lone SS3 with nothing after it is Alt+Shift+O, never a focus report.
+    let (_, ev) = parse_input("\u{1b}O", false).unwrap();
+    assert_eq!(
+        ev,
+        InputEvent::Key(KeyEvent::new(KeyCode::Char('O'), Modifiers::ALT))
+    );
+}
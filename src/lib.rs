@@ -5,9 +5,27 @@
 #![recursion_limit = "256"]
 #![cfg_attr(not(any(feature = "std", test)), no_std)]
 
+#[cfg(any(feature = "std", test))]
+mod cell;
+mod charset;
 mod enums;
+#[cfg(any(feature = "std", test))]
+mod flatten;
+#[cfg(any(feature = "std", test))]
+mod frames;
+mod input;
+mod modes;
+mod optimize;
+#[cfg(any(feature = "std", test))]
+mod overstrike;
 mod parsers;
+#[cfg(any(feature = "std", test))]
+mod screen;
+#[cfg(any(feature = "std", test))]
+mod strip;
+mod style;
 mod traits;
+mod vt52;
 
 ///This is a library for parsing ANSI escape sequences. Currently all the basic escape sequences
 ///are implemented:
@@ -22,6 +40,29 @@ mod traits;
 /// This is done through a pulldown type parser, where an iterator is exposed. This essentially
 /// turns all of the ANSI sequences into enums and splits the string at every location that there
 /// was an ANSI Sequence.
+#[cfg(any(feature = "std", test))]
+pub use cell::Cell;
+#[cfg(any(feature = "std", test))]
+pub use charset::CharsetIterator;
+pub use charset::{Charset, CharsetState};
 pub use enums::*;
+#[cfg(any(feature = "std", test))]
+pub use flatten::flatten;
+#[cfg(any(feature = "std", test))]
+pub use frames::{Frame, FrameItem, FrameIterator};
+pub use input::{
+    parse_input, parse_key, parse_kitty_key, parse_mouse, InputDecoder, InputEvent, KeyCode,
+    KeyEvent, KeyEventKind, KittyKeyEvent, Modifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+pub use modes::{Mode, ModeState};
+pub use optimize::optimize;
+#[cfg(any(feature = "std", test))]
+pub use overstrike::OverstrikeIterator;
 pub use parsers::parse_escape;
+#[cfg(any(feature = "std", test))]
+pub use screen::{ExpectedSnapshot, Screen, Snapshot, SnapshotError};
+#[cfg(any(feature = "std", test))]
+pub use strip::{strip, StripAction, StripPolicy};
+pub use style::{Color, SgrDiff, Style, StyleState, StyledIterator};
 pub use traits::*;
+pub use vt52::{parse_vt52, ModalOutput, ModalParseIterator, Vt52Sequence};
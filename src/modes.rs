@@ -0,0 +1,453 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+#[cfg(test)]
+mod tests;
+
+use crate::enums::{AnsiSequence, Output};
+use crate::vt52::{ModalOutput, Vt52Sequence};
+
+use core::fmt::{Result as DisplayResult, Write};
+
+///A terminal mode that [`ModeState`] keeps track of. All but `NewLine` are DEC private modes.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Mode {
+    ///LNM, a line feed also returns to the first column.
+    NewLine,
+    ///DECCKM, the cursor keys send application sequences.
+    CursorKeys,
+    ///DECANM, off in VT52 mode.
+    Ansi,
+    ///DECCOLM, 132 columns instead of 80.
+    Column132,
+    ///DECSCLM, smooth instead of jump scrolling.
+    SmoothScroll,
+    ///DECSCNM, reverse video for the whole screen.
+    ReverseVideo,
+    ///DECOM, cursor addresses count from the top margin.
+    Origin,
+    ///DECAWM.
+    AutoWrap,
+    ///DECARM.
+    AutoRepeat,
+    ///DECINLM.
+    Interlace,
+    ///DECTCEM.
+    CursorVisible,
+    ///The alternate screen, however it was entered.
+    AlternateScreen,
+    FocusReporting,
+    BracketedPaste,
+    SynchronizedUpdate,
+    ///DECKPAM, the keypad sends application sequences.
+    AlternateKeypad,
+    ///Mouse button reports, `?1000`.
+    MouseTracking,
+    ///Mouse reports of motion with a button down, `?1002`.
+    ButtonEventTracking,
+    ///Mouse reports of all motion, `?1003`.
+    AnyEventTracking,
+    ///Mouse reports in the SGR format, `?1006`.
+    SgrMouse,
+}
+
+const MODE_COUNT: usize = 20;
+
+impl Mode {
+    pub const ALL: [Mode; MODE_COUNT] = [
+        Mode::NewLine,
+        Mode::CursorKeys,
+        Mode::Ansi,
+        Mode::Column132,
+        Mode::SmoothScroll,
+        Mode::ReverseVideo,
+        Mode::Origin,
+        Mode::AutoWrap,
+        Mode::AutoRepeat,
+        Mode::Interlace,
+        Mode::CursorVisible,
+        Mode::AlternateScreen,
+        Mode::FocusReporting,
+        Mode::BracketedPaste,
+        Mode::SynchronizedUpdate,
+        Mode::AlternateKeypad,
+        Mode::MouseTracking,
+        Mode::ButtonEventTracking,
+        Mode::AnyEventTracking,
+        Mode::SgrMouse,
+    ];
+
+    ///How the mode is after a reset, as xterm has it.
+    pub fn default_value(self) -> bool {
+        matches!(
+            self,
+            Mode::Ansi | Mode::AutoWrap | Mode::AutoRepeat | Mode::CursorVisible
+        )
+    }
+
+    ///Writes the sequence that turns the mode on or off. The alternate screen is left with
+    ///`?1049`, which also restores the cursor saved on the way in.
+    pub fn write_sequence<W: Write>(self, on: bool, writer: &mut W) -> DisplayResult {
+        use AnsiSequence::*;
+
+        let seq = match (self, on) {
+            (Mode::NewLine, true) => SetNewLineMode,
+            (Mode::NewLine, false) => SetLineFeedMode,
+            (Mode::CursorKeys, true) => CursorToApp,
+            (Mode::CursorKeys, false) => SetCursorKeyToCursor,
+            //Going back to ANSI is a VT52 sequence.
+            (Mode::Ansi, true) => return write!(writer, "{}", Vt52Sequence::EnterAnsiMode),
+            (Mode::Ansi, false) => SetVT52,
+            (Mode::Column132, true) => SetCol132,
+            (Mode::Column132, false) => SetCol80,
+            (Mode::SmoothScroll, true) => SetSmoothScroll,
+            (Mode::SmoothScroll, false) => SetJumpScrolling,
+            (Mode::ReverseVideo, true) => SetReverseVideo,
+            (Mode::ReverseVideo, false) => SetNormalVideo,
+            (Mode::Origin, true) => SetOriginRelative,
+            (Mode::Origin, false) => SetOriginAbsolute,
+            (Mode::AutoWrap, true) => SetAutoWrap,
+            (Mode::AutoWrap, false) => ResetAutoWrap,
+            (Mode::AutoRepeat, true) => SetAutoRepeat,
+            (Mode::AutoRepeat, false) => ResetAutoRepeat,
+            (Mode::Interlace, true) => SetInterlacing,
+            (Mode::Interlace, false) => ResetInterlacing,
+            (Mode::CursorVisible, true) => ShowCursor,
+            (Mode::CursorVisible, false) => HideCursor,
+            (Mode::AlternateScreen, true) => SetAlternateScreenSaveCursor,
+            (Mode::AlternateScreen, false) => ResetAlternateScreenSaveCursor,
+            (Mode::FocusReporting, true) => SetFocusReporting,
+            (Mode::FocusReporting, false) => ResetFocusReporting,
+            (Mode::BracketedPaste, true) => SetBracketedPaste,
+            (Mode::BracketedPaste, false) => ResetBracketedPaste,
+            (Mode::SynchronizedUpdate, true) => SetSynchronizedUpdate,
+            (Mode::SynchronizedUpdate, false) => ResetSynchronizedUpdate,
+            (Mode::AlternateKeypad, true) => SetAlternateKeypad,
+            (Mode::AlternateKeypad, false) => SetNumericKeypad,
+            (Mode::MouseTracking, true) => SetMouseTracking,
+            (Mode::MouseTracking, false) => ResetMouseTracking,
+            (Mode::ButtonEventTracking, true) => SetButtonEventTracking,
+            (Mode::ButtonEventTracking, false) => ResetButtonEventTracking,
+            (Mode::AnyEventTracking, true) => SetAnyEventTracking,
+            (Mode::AnyEventTracking, false) => ResetAnyEventTracking,
+            (Mode::SgrMouse, true) => SetSgrMouse,
+            (Mode::SgrMouse, false) => ResetSgrMouse,
+        };
+        write!(writer, "{}", seq)
+    }
+}
+
+//One bit for each of the 256 screen modes of `SetMode` and `ResetMode`.
+type ScreenModes = [u32; 8];
+
+fn bit(bits: &ScreenModes, n: u8) -> bool {
+    bits[n as usize / 32] & (1 << (n % 32)) != 0
+}
+
+fn set_bit(bits: &mut ScreenModes, n: u8, on: bool) {
+    if on {
+        bits[n as usize / 32] |= 1 << (n % 32);
+    } else {
+        bits[n as usize / 32] &= !(1 << (n % 32));
+    }
+}
+
+//The kitty keyboard protocol keeps a stack of flags for each screen, dropping the oldest entry
+//when it is full.
+const KEYBOARD_STACK: usize = 16;
+
+//The flags pushed onto a keyboard stack since the stream began, oldest first. Entries that
+//were there before can not be known, so popping them is not tracked.
+#[derive(Debug, Default, Eq, Clone, Copy)]
+struct KeyboardStack {
+    flags: [u8; KEYBOARD_STACK],
+    len: usize,
+}
+
+//What was popped is left behind in `flags`, and does not count.
+impl PartialEq for KeyboardStack {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries() == other.entries()
+    }
+}
+
+impl KeyboardStack {
+    fn entries(&self) -> &[u8] {
+        &self.flags[..self.len]
+    }
+
+    fn push(&mut self, flags: u8) {
+        if self.len == KEYBOARD_STACK {
+            self.flags.copy_within(1.., 0);
+            self.len -= 1;
+        }
+        self.flags[self.len] = flags;
+        self.len += 1;
+    }
+
+    fn pop(&mut self, n: u32) {
+        self.len = self.len.saturating_sub(n as usize);
+    }
+
+    //Writes the pops and pushes that turn this stack into `target`.
+    fn write_change<W: Write>(&self, target: &KeyboardStack, writer: &mut W) -> DisplayResult {
+        let common = self
+            .entries()
+            .iter()
+            .zip(target.entries())
+            .take_while(|(a, b)| a == b)
+            .count();
+        if self.len > common {
+            let seq = AnsiSequence::PopKeyboardFlags((self.len - common) as u32);
+            write!(writer, "{}", seq)?;
+        }
+        for &flags in &target.entries()[common..] {
+            write!(writer, "{}", AnsiSequence::PushKeyboardFlags(flags))?;
+        }
+        Ok(())
+    }
+}
+
+//The XTMODKEYS resources, `modifyKeyboard` through `modifyOtherKeys`.
+const KEY_MODIFIER_COUNT: usize = 5;
+
+///The modes a terminal is in, as far as a stream of sequences has set them. A mode that the
+///stream never touched is unknown, unless the state started out from [`ModeState::defaults`].
+///
+///The kitty keyboard flags pushed on each screen and the XTMODKEYS key modifier options are
+///kept as well.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct ModeState {
+    modes: [Option<bool>; MODE_COUNT],
+    screen_known: ScreenModes,
+    screen_set: ScreenModes,
+    //The kitty keyboard flags pushed on the main and on the alternate screen.
+    keyboard: [KeyboardStack; 2],
+    //Each XTMODKEYS resource as last set, `Some(None)` when it was reset to its default.
+    key_modifiers: [Option<Option<u8>>; KEY_MODIFIER_COUNT],
+}
+
+impl ModeState {
+    ///Nothing known about any mode.
+    pub fn new() -> Self {
+        ModeState::default()
+    }
+
+    ///Every mode as it is after a reset, with no screen modes set and the key modifier
+    ///options at their defaults.
+    pub fn defaults() -> Self {
+        let mut state = ModeState::new();
+        for &mode in Mode::ALL.iter() {
+            state.set(mode, mode.default_value());
+        }
+        state.key_modifiers = [Some(None); KEY_MODIFIER_COUNT];
+        state
+    }
+
+    ///Whether the mode is on, `None` when it is unknown.
+    pub fn get(&self, mode: Mode) -> Option<bool> {
+        self.modes[mode as usize]
+    }
+
+    pub fn set(&mut self, mode: Mode, on: bool) {
+        self.modes[mode as usize] = Some(on);
+    }
+
+    ///Whether a screen mode from `SetMode` or `ResetMode` is on, `None` when it is unknown.
+    pub fn screen_mode(&self, n: u8) -> Option<bool> {
+        if bit(&self.screen_known, n) {
+            Some(bit(&self.screen_set, n))
+        } else {
+            None
+        }
+    }
+
+    pub fn set_screen_mode(&mut self, n: u8, on: bool) {
+        set_bit(&mut self.screen_known, n, true);
+        set_bit(&mut self.screen_set, n, on);
+    }
+
+    ///The kitty keyboard flags pushed since the stream began on the screen that is shown,
+    ///oldest first.
+    pub fn keyboard_flags(&self) -> &[u8] {
+        self.keyboard[self.screen()].entries()
+    }
+
+    ///The value an XTMODKEYS resource was set to, `Some(None)` when it was reset and `None`
+    ///when it is unknown.
+    pub fn key_modifier(&self, resource: u8) -> Option<Option<u8>> {
+        self.key_modifiers.get(resource as usize).copied().flatten()
+    }
+
+    fn set_key_modifier(&mut self, resource: u8, value: Option<u8>) {
+        if let Some(slot) = self.key_modifiers.get_mut(resource as usize) {
+            *slot = Some(value);
+        }
+    }
+
+    fn screen(&self) -> usize {
+        (self.get(Mode::AlternateScreen) == Some(true)) as usize
+    }
+
+    fn keyboard_mut(&mut self) -> &mut KeyboardStack {
+        let screen = self.screen();
+        &mut self.keyboard[screen]
+    }
+
+    ///Updates the state from a sequence, anything that does not change a mode is ignored.
+    pub fn apply(&mut self, seq: &AnsiSequence) {
+        use AnsiSequence::*;
+
+        let (mode, on) = match *seq {
+            SetMode(n) => return self.set_screen_mode(n, true),
+            ResetMode(n) => return self.set_screen_mode(n, false),
+            SetNewLineMode => (Mode::NewLine, true),
+            SetLineFeedMode => (Mode::NewLine, false),
+            CursorToApp => (Mode::CursorKeys, true),
+            SetCursorKeyToCursor => (Mode::CursorKeys, false),
+            SetVT52 => (Mode::Ansi, false),
+            SetCol132 => (Mode::Column132, true),
+            SetCol80 => (Mode::Column132, false),
+            SetSmoothScroll => (Mode::SmoothScroll, true),
+            SetJumpScrolling => (Mode::SmoothScroll, false),
+            SetReverseVideo => (Mode::ReverseVideo, true),
+            SetNormalVideo => (Mode::ReverseVideo, false),
+            SetOriginRelative => (Mode::Origin, true),
+            SetOriginAbsolute => (Mode::Origin, false),
+            SetAutoWrap => (Mode::AutoWrap, true),
+            ResetAutoWrap => (Mode::AutoWrap, false),
+            SetAutoRepeat => (Mode::AutoRepeat, true),
+            ResetAutoRepeat => (Mode::AutoRepeat, false),
+            SetInterlacing => (Mode::Interlace, true),
+            ResetInterlacing => (Mode::Interlace, false),
+            ShowCursor => (Mode::CursorVisible, true),
+            HideCursor => (Mode::CursorVisible, false),
+            SetAlternateScreen | SetAlternateScreenClear | SetAlternateScreenSaveCursor => {
+                (Mode::AlternateScreen, true)
+            }
+            ResetAlternateScreen | ResetAlternateScreenClear | ResetAlternateScreenSaveCursor => {
+                (Mode::AlternateScreen, false)
+            }
+            SetFocusReporting => (Mode::FocusReporting, true),
+            ResetFocusReporting => (Mode::FocusReporting, false),
+            SetBracketedPaste => (Mode::BracketedPaste, true),
+            ResetBracketedPaste => (Mode::BracketedPaste, false),
+            SetSynchronizedUpdate => (Mode::SynchronizedUpdate, true),
+            ResetSynchronizedUpdate => (Mode::SynchronizedUpdate, false),
+            SetAlternateKeypad => (Mode::AlternateKeypad, true),
+            SetNumericKeypad => (Mode::AlternateKeypad, false),
+            SetMouseTracking => (Mode::MouseTracking, true),
+            ResetMouseTracking => (Mode::MouseTracking, false),
+            SetButtonEventTracking => (Mode::ButtonEventTracking, true),
+            ResetButtonEventTracking => (Mode::ButtonEventTracking, false),
+            SetAnyEventTracking => (Mode::AnyEventTracking, true),
+            ResetAnyEventTracking => (Mode::AnyEventTracking, false),
+            SetSgrMouse => (Mode::SgrMouse, true),
+            ResetSgrMouse => (Mode::SgrMouse, false),
+            PushKeyboardFlags(flags) => return self.keyboard_mut().push(flags),
+            PopKeyboardFlags(n) => return self.keyboard_mut().pop(n),
+            SetKeyModifierOption(res, val) => return self.set_key_modifier(res, Some(val)),
+            ResetKeyModifierOption(res) => return self.set_key_modifier(res, None),
+            _ => return,
+        };
+        self.set(mode, on);
+    }
+
+    ///Updates the state from a sequence of VT52 mode.
+    pub fn apply_vt52(&mut self, seq: &Vt52Sequence) {
+        match seq {
+            Vt52Sequence::EnterAnsiMode => self.set(Mode::Ansi, true),
+            Vt52Sequence::SetAlternateKeypad => self.set(Mode::AlternateKeypad, true),
+            Vt52Sequence::SetNumericKeypad => self.set(Mode::AlternateKeypad, false),
+            _ => {}
+        }
+    }
+
+    ///Writes the sequences that take a terminal in this state to `target`. Modes that are
+    ///unknown in `target` are left alone, and the ones that are unknown here are written out.
+    ///Kitty keyboard flags that only this state pushed are popped, and the ones that only
+    ///`target` pushed are pushed.
+    ///
+    ///After a child process died, restoring to the state from before it started, or to
+    ///[`ModeState::defaults`], puts the terminal back in order.
+    pub fn restore<W: Write>(&self, target: &ModeState, writer: &mut W) -> DisplayResult {
+        let changed = |mode: Mode| match target.get(mode) {
+            Some(on) if self.get(mode) != Some(on) => Some(on),
+            _ => None,
+        };
+
+        //Nothing else is understood in VT52 mode, so that has to be left first and entered last.
+        if changed(Mode::Ansi) == Some(true) {
+            Mode::Ansi.write_sequence(true, writer)?;
+        }
+        //The keyboard flags belong to a screen, so they are put right on the screen that is
+        //shown before switching, and on the other one after.
+        let screen = self.screen();
+        self.keyboard[screen].write_change(&target.keyboard[screen], writer)?;
+        for &mode in Mode::ALL.iter().filter(|&&mode| mode != Mode::Ansi) {
+            if let Some(on) = changed(mode) {
+                mode.write_sequence(on, writer)?;
+            }
+        }
+        let switched = match target.get(Mode::AlternateScreen) {
+            Some(alternate) => alternate as usize,
+            None => screen,
+        };
+        if switched != screen {
+            self.keyboard[switched].write_change(&target.keyboard[switched], writer)?;
+        }
+        for (resource, &value) in target.key_modifiers.iter().enumerate() {
+            match value {
+                Some(value) if self.key_modifiers[resource] != Some(value) => {
+                    let resource = resource as u8;
+                    let seq = match value {
+                        Some(value) => AnsiSequence::SetKeyModifierOption(resource, value),
+                        None => AnsiSequence::ResetKeyModifierOption(resource),
+                    };
+                    write!(writer, "{}", seq)?;
+                }
+                _ => {}
+            }
+        }
+        for n in 0..=u8::MAX {
+            match target.screen_mode(n) {
+                Some(on) if self.screen_mode(n) != Some(on) => {
+                    let seq = if on {
+                        AnsiSequence::SetMode(n)
+                    } else {
+                        AnsiSequence::ResetMode(n)
+                    };
+                    write!(writer, "{}", seq)?;
+                }
+                _ => {}
+            }
+        }
+        if changed(Mode::Ansi) == Some(false) {
+            Mode::Ansi.write_sequence(false, writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Extend<Output<'a>> for ModeState {
+    fn extend<I: IntoIterator<Item = Output<'a>>>(&mut self, outputs: I) {
+        for output in outputs {
+            if let Output::Escape(seq) = output {
+                self.apply(&seq);
+            }
+        }
+    }
+}
+
+impl<'a> Extend<ModalOutput<'a>> for ModeState {
+    fn extend<I: IntoIterator<Item = ModalOutput<'a>>>(&mut self, outputs: I) {
+        for output in outputs {
+            match output {
+                ModalOutput::Escape(seq) => self.apply(&seq),
+                ModalOutput::Vt52(seq) => self.apply_vt52(&seq),
+                ModalOutput::TextBlock(_) => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,150 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+use super::*;
+use crate::traits::AnsiParser;
+use crate::vt52::ModalParseIterator;
+
+use std::string::String;
+
+fn state(input: &str) -> ModeState {
+    let mut state = ModeState::new();
+    state.extend(input.ansi_parse());
+    state
+}
+
+fn restore(from: &ModeState, to: &ModeState) -> String {
+    let mut out = String::new();
+    from.restore(to, &mut out).unwrap();
+    out
+}
+
+#[test]
+fn test_unknown_until_set() {
+    let state = state("text \x1b[1mbold");
+    for &mode in Mode::ALL.iter() {
+        assert_eq!(state.get(mode), None);
+    }
+    assert_eq!(state.screen_mode(7), None);
+}
+
+#[test]
+fn test_modes_follow_the_stream() {
+    let state = state("\x1b[?1h\x1b[?25l\x1b[?7l\x1b[?5h\x1b[?5l\x1b[20h\x1b=\x1b[?2004h");
+    assert_eq!(state.get(Mode::CursorKeys), Some(true));
+    assert_eq!(state.get(Mode::CursorVisible), Some(false));
+    assert_eq!(state.get(Mode::AutoWrap), Some(false));
+    assert_eq!(state.get(Mode::ReverseVideo), Some(false));
+    assert_eq!(state.get(Mode::NewLine), Some(true));
+    assert_eq!(state.get(Mode::AlternateKeypad), Some(true));
+    assert_eq!(state.get(Mode::BracketedPaste), Some(true));
+    assert_eq!(state.get(Mode::Origin), None);
+}
+
+#[test]
+fn test_every_alternate_screen_variant() {
+    for input in ["\x1b[?47h", "\x1b[?1047h", "\x1b[?1049h"].iter() {
+        assert_eq!(state(input).get(Mode::AlternateScreen), Some(true));
+    }
+    assert_eq!(
+        state("\x1b[?1049h\x1b[?47l").get(Mode::AlternateScreen),
+        Some(false)
+    );
+}
+
+#[test]
+fn test_screen_modes() {
+    let state = state("\x1b[=7h\x1b[=13h\x1b[=13l");
+    assert_eq!(state.screen_mode(7), Some(true));
+    assert_eq!(state.screen_mode(13), Some(false));
+    assert_eq!(state.screen_mode(255), None);
+}
+
+#[test]
+fn test_defaults() {
+    let state = ModeState::defaults();
+    assert_eq!(state.get(Mode::AutoWrap), Some(true));
+    assert_eq!(state.get(Mode::CursorVisible), Some(true));
+    assert_eq!(state.get(Mode::Ansi), Some(true));
+    assert_eq!(state.get(Mode::Column132), Some(false));
+    assert_eq!(state.screen_mode(3), None);
+}
+
+#[test]
+fn test_restore_after_crash() {
+    let before = ModeState::defaults();
+    let mut after = before;
+    after.extend("\x1b[?1049h\x1b[?25l\x1b[?1h\x1b=\x1b[?2004hpartial output".ansi_parse());
+
+    assert_eq!(
+        restore(&after, &before),
+        "\x1b[?1l\x1b[?25h\x1b[?1049l\x1b[?2004l\x1b>"
+    );
+    assert_eq!(restore(&before, &before), "");
+}
+
+#[test]
+fn test_restore_keyboard_and_mouse_after_crash() {
+    let before = ModeState::defaults();
+    let mut after = before;
+    after.extend(
+        "\x1b[>1u\x1b[?1000h\x1b[?1006h\x1b[>4;2m\x1b[?1049h\x1b[>3u\x1b[>11u\x1b[<u\x1b[?1003h"
+            .ansi_parse(),
+    );
+    assert_eq!(after.keyboard_flags(), &[3]);
+    assert_eq!(after.key_modifier(4), Some(Some(2)));
+    assert_eq!(after.get(Mode::AnyEventTracking), Some(true));
+
+    //The alternate screen's flags are popped before leaving it, the main screen's after.
+    assert_eq!(
+        restore(&after, &before),
+        "\x1b[<1u\x1b[?1049l\x1b[?1000l\x1b[?1003l\x1b[?1006l\x1b[<1u\x1b[>4m"
+    );
+    let mut restored = after;
+    restored.extend(restore(&after, &before).ansi_parse());
+    assert_eq!(restored.keyboard_flags(), &[] as &[u8]);
+    assert_eq!(restored, before);
+}
+
+#[test]
+fn test_restore_pushes_keyboard_flags() {
+    let before = state("\x1b[>1u\x1b[>5u");
+    let after = state("\x1b[>1u\x1b[>7u\x1b[>9u");
+    assert_eq!(restore(&after, &before), "\x1b[<2u\x1b[>5u");
+    assert_eq!(restore(&before, &after), "\x1b[<1u\x1b[>7u\x1b[>9u");
+}
+
+#[test]
+fn test_restore_writes_what_is_unknown() {
+    let target = state("\x1b[?7h\x1b[=4l");
+    assert_eq!(restore(&ModeState::new(), &target), "\x1b[?7h\x1b[=4l");
+    //Modes the target does not know are left as they are.
+    assert_eq!(restore(&state("\x1b[?7h\x1b[?6h"), &target), "\x1b[=4l");
+}
+
+#[test]
+fn test_restore_leaves_vt52_first() {
+    let mut after = ModeState::defaults();
+    after.extend(ModalParseIterator::new("\x1b[?25l\x1b[?2l\x1bH\x1b="));
+    assert_eq!(after.get(Mode::Ansi), Some(false));
+    assert_eq!(after.get(Mode::AlternateKeypad), Some(true));
+    assert_eq!(
+        restore(&after, &ModeState::defaults()),
+        "\x1b<\x1b[?25h\x1b>"
+    );
+    assert_eq!(
+        restore(&ModeState::defaults(), &after),
+        "\x1b[?25l\x1b=\x1b[?2l"
+    );
+
+    after.extend(ModalParseIterator::new("\x1b<").with_vt52(true));
+    assert_eq!(after.get(Mode::Ansi), Some(true));
+}
+
+#[test]
+fn test_restore_round_trips() {
+    let target = state("\x1b[?3h\x1b[?4h\x1b[?6h\x1b[?8l\x1b[?9h\x1b[?1004h\x1b[?2026h");
+    let out = restore(&ModeState::defaults(), &target);
+    assert_eq!(state(&out), target);
+}
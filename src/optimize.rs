@@ -0,0 +1,178 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+#[cfg(test)]
+mod tests;
+
+use crate::enums::{AnsiSequence, Output};
+use crate::style::{models_sgr, resets_sgr, StyleState};
+
+use core::fmt::{Result as DisplayResult, Write};
+
+//Erasing fills cells with the current background colour, and saving the cursor saves the pen
+//with it, so the pen has to be up to date before these are written.
+fn uses_pen(seq: &AnsiSequence) -> bool {
+    use AnsiSequence::*;
+
+    matches!(
+        seq,
+        EraseDisplay
+            | EraseLine
+            | SetCol132
+            | SetCol80
+            | ResetAlternateScreenClear
+            | CursorSave
+            | SaveCursor
+            | SetAlternateScreenSaveCursor
+    )
+}
+
+//A pen saved along with the cursor.
+#[derive(Debug, Default, Clone, Copy)]
+struct SavedPen {
+    state: StyleState,
+    untracked: bool,
+}
+
+//The pen as last written to the terminal, and as the stream wants it for what comes next.
+#[derive(Debug, Default)]
+struct Pen {
+    written: StyleState,
+    pending: StyleState,
+    //An attribute that `Style` does not track may be on, so only a reset from the stream
+    //itself may clear it.
+    untracked: bool,
+    //The stream reset the pen while `untracked` was set.
+    reset: bool,
+    //The main and the alternate screen each save their own cursor, as in `Screen`.
+    saved: [SavedPen; 2],
+    alternate: bool,
+}
+
+impl Pen {
+    fn apply(&mut self, seq: &AnsiSequence) {
+        if let AnsiSequence::SetGraphicsMode(params) = seq {
+            if self.untracked && resets_sgr(params) {
+                self.reset = true;
+            }
+        }
+        self.pending.apply(seq);
+    }
+
+    //Follows the sequences that save and restore the pen, or switch the screen whose saved
+    //cursor they use. A save comes after a flush, so the terminal has exactly the pen that is
+    //saved, and a restore brings back both that and the stream's own pen.
+    fn track(&mut self, seq: &AnsiSequence) {
+        use AnsiSequence::*;
+
+        match seq {
+            CursorSave | SaveCursor => self.save(),
+            CursorRestore | RestoreCursor => self.restore(),
+            SetAlternateScreenSaveCursor => {
+                if !self.alternate {
+                    self.save();
+                }
+                self.alternate = true;
+            }
+            ResetAlternateScreenSaveCursor => {
+                self.alternate = false;
+                self.restore();
+            }
+            SetAlternateScreen | SetAlternateScreenClear => self.alternate = true,
+            ResetAlternateScreen | ResetAlternateScreenClear => self.alternate = false,
+            _ => {}
+        }
+    }
+
+    fn save(&mut self) {
+        self.saved[self.alternate as usize] = SavedPen {
+            state: self.written,
+            untracked: self.untracked,
+        };
+    }
+
+    //Without a save the terminal goes back to the default pen.
+    fn restore(&mut self) {
+        let saved = self.saved[self.alternate as usize];
+        self.written = saved.state;
+        self.pending = saved.state;
+        self.untracked = saved.untracked;
+        self.reset = false;
+    }
+
+    fn flush<W: Write>(&mut self, writer: &mut W) -> DisplayResult {
+        if self.reset {
+            write!(
+                writer,
+                "{}",
+                AnsiSequence::SetGraphicsMode(heapless::Vec::new())
+            )?;
+            self.written = StyleState::new();
+            self.untracked = false;
+            self.reset = false;
+        }
+
+        let (from, to) = (self.written.style(), self.pending.style());
+        let diff = if self.untracked {
+            from.diff_without_reset(&to)
+        } else {
+            from.diff(&to)
+        };
+        //Split up, as the parser takes at most five parameters in one sequence.
+        if let Some(diff) = diff {
+            for seq in diff.sequences() {
+                write!(writer, "{}", seq)?;
+            }
+        }
+        self.written = self.pending;
+        Ok(())
+    }
+}
+
+///Writes `outputs` to `writer` without redundant escape sequences.
+///
+///Style changes are held back until some text, or an erase that paints with the background
+///colour, depends on them, and are then written as the shortest SGR change from the previous
+///pen, split into sequences of at most five parameters so that the parser reads them back.
+///Runs of text end up next to each other, and the pen is brought up to date once more at the
+///end, so the terminal is left in the same state as with the original stream.
+///SGR attributes that [`Style`](crate::Style) does not track are passed through as they are.
+///Sequences that save the cursor save the pen with it, so they also get the pen up to date, and
+///a restore takes the pen back to what was saved.
+pub fn optimize<'a, I, W>(outputs: I, writer: &mut W) -> DisplayResult
+where
+    I: IntoIterator<Item = Output<'a>>,
+    W: Write,
+{
+    let mut pen = Pen::default();
+
+    for output in outputs {
+        match output {
+            Output::Escape(AnsiSequence::SetGraphicsMode(ref params)) if !models_sgr(params) => {
+                pen.flush(writer)?;
+                write!(writer, "{}", output)?;
+                if let Output::Escape(seq) = &output {
+                    pen.pending.apply(seq);
+                }
+                pen.written = pen.pending;
+                pen.untracked = true;
+            }
+            Output::Escape(seq @ AnsiSequence::SetGraphicsMode(_)) => pen.apply(&seq),
+            Output::Escape(seq) => {
+                if uses_pen(&seq) {
+                    pen.flush(writer)?;
+                }
+                write!(writer, "{}", seq)?;
+                pen.track(&seq);
+            }
+            Output::TextBlock("") => {}
+            Output::TextBlock(text) => {
+                pen.flush(writer)?;
+                writer.write_str(text)?;
+            }
+        }
+    }
+
+    pen.flush(writer)
+}
@@ -0,0 +1,175 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+use super::*;
+use crate::screen::Screen;
+use crate::style::Style;
+use crate::traits::AnsiParser;
+
+const LOLCAT: &str = "\u{1b}[38;2;51;254;77mS\u{1b}[39m\u{1b}[38;2;52;254;77mt\u{1b}[39m\u{1b}[38;2;52;254;77ma\u{1b}[39m\u{1b}[38;2;52;254;76mt\u{1b}[39m\u{1b}[38;2;53;254;76me\u{1b}[39m\u{1b}[38;2;53;254;76m \u{1b}[39m\u{1b}[38;2;53;254;75m{\u{1b}[39m\u{1b}[38;2;54;254;75m \u{1b}[39m\u{1b}[38;2;54;254;74ms\u{1b}[39m\u{1b}[38;2;54;254;74mt\u{1b}[39m\u{1b}[38;2;55;254;74ma\u{1b}[39m\u{1b}[38;2;55;254;73mc\u{1b}[39m\u{1b}[38;2;56;254;73mk\u{1b}[39m\u{1b}[38;2;56;254;72m:\u{1b}[39m\u{1b}[38;2;56;254;72m \u{1b}[39m\u{1b}[38;2;57;254;72m[\u{1b}[39m\u{1b}[38;2;57;254;71m0\u{1b}[39m\u{1b}[38;2;57;254;71m]\u{1b}[39m\u{1b}[38;2;58;254;71m \u{1b}[39m\u{1b}[38;2;58;254;70m}\u{1b}[39m";
+
+fn optimized(input: &str) -> String {
+    let mut buff = String::new();
+    optimize(input.ansi_parse(), &mut buff).unwrap();
+    buff
+}
+
+//Everything a terminal would show: every character with its style, every other sequence in
+//order, and the pen that is left at the end.
+fn rendering(input: &str) -> (Vec<String>, Style) {
+    let mut state = StyleState::new();
+    let mut out = Vec::new();
+    for part in input.ansi_parse() {
+        match part {
+            Output::TextBlock(text) => {
+                for c in text.chars() {
+                    out.push(format!("{:?} {}", state.style(), c));
+                }
+            }
+            Output::Escape(seq @ AnsiSequence::SetGraphicsMode(_)) => state.apply(&seq),
+            Output::Escape(seq) if uses_pen(&seq) => {
+                out.push(format!("{:?} {}", state.style(), seq))
+            }
+            Output::Escape(seq) => out.push(seq.to_string()),
+        }
+    }
+    (out, state.style())
+}
+
+fn check(input: &str) -> String {
+    let output = optimized(input);
+    assert_eq!(rendering(input), rendering(&output));
+    assert!(output.len() <= input.len());
+    output
+}
+
+//Both streams have to draw the same screen, for the sequences that are written as parsed.
+fn check_screen(input: &str, rows: usize, cols: usize) -> String {
+    let output = optimized(input);
+    let (mut expected, mut drawn) = (Screen::new(rows, cols), Screen::new(rows, cols));
+    expected.process(input);
+    drawn.process(&output);
+    assert_eq!(
+        drawn.cells().collect::<Vec<_>>(),
+        expected.cells().collect::<Vec<_>>()
+    );
+    assert_eq!(drawn.cursor(), expected.cursor());
+    output
+}
+
+#[test]
+fn test_lolcat() {
+    let output = check(LOLCAT);
+    assert!(output.starts_with("\u{1b}[38;2;51;254;77mS\u{1b}[38;2;52;254;77mt"));
+    assert!(output.ends_with("}\u{1b}[m"));
+    assert!(!output.contains("\u{1b}[39m"));
+}
+
+#[test]
+fn test_collapses_consecutive_sgr() {
+    assert_eq!(
+        check("\x1b[1m\x1b[31m\x1b[4mhi\x1b[0m"),
+        "\x1b[1;4;31mhi\x1b[m"
+    );
+}
+
+#[test]
+fn test_drops_unused_changes() {
+    assert_eq!(check("a\x1b[31m\x1b[0mb\x1b[0m\x1b[0m"), "ab");
+    assert_eq!(check("\x1b[32m\x1b[33mx\x1b[33my"), "\x1b[33mxy");
+}
+
+#[test]
+fn test_merges_text() {
+    let output = check("one\x1b[39m two\x1b[m three");
+    assert_eq!(output, "one two three");
+    assert_eq!(output.ansi_parse().count(), 1);
+}
+
+#[test]
+fn test_keeps_background_for_erase() {
+    assert_eq!(check("\x1b[44m\x1b[2J\x1b[0m"), "\x1b[44m\x1b[2J\x1b[m");
+    assert_eq!(check("\x1b[1m\x1b[H\x1b[22mx"), "\x1b[Hx");
+}
+
+#[test]
+fn test_keeps_final_pen() {
+    assert_eq!(check("x\x1b[1m\x1b[31m"), "x\x1b[1;31m");
+}
+
+#[test]
+fn test_passes_through_unknown_attributes() {
+    assert_eq!(
+        check("\x1b[31m\x1b[53mover\x1b[55m\x1b[0m"),
+        "\x1b[31m\x1b[53mover\x1b[55m\x1b[m"
+    );
+}
+
+#[test]
+fn test_reset_clears_unknown_attributes() {
+    assert_eq!(optimized("\x1b[53mA\x1b[0mB"), "\x1b[53mA\x1b[mB");
+    assert_eq!(
+        optimized("\x1b[53m\x1b[1;3;4;31mA\x1b[22;23;24m\x1b[35mB"),
+        "\x1b[53m\x1b[1;3;4;31mA\x1b[22;23;24;35mB"
+    );
+}
+
+//A restore takes the pen back to what was saved with the cursor, which `rendering` does not
+//follow, so these are checked as they are written.
+#[test]
+fn test_restore_brings_back_saved_pen() {
+    assert_eq!(
+        optimized("\x1b[31m\x1b7\x1b[0mx\x1b8y"),
+        "\x1b[31m\x1b7\x1b[mx\x1b8y"
+    );
+    assert_eq!(
+        optimized("\x1b[1m\x1b[s\x1b[0;32mx\x1b[u\x1b[1my"),
+        "\x1b[1m\x1b[s\x1b[0;32mx\x1b[uy"
+    );
+    //Without a save the pen goes back to the default.
+    assert_eq!(optimized("\x1b[31mx\x1b[uy"), "\x1b[31mx\x1b[uy");
+}
+
+#[test]
+fn test_alternate_screen_restores_pen() {
+    assert_eq!(
+        optimized("\x1b[1m\x1b[?1049h\x1b[0;32mx\x1b[?1049ly"),
+        "\x1b[1m\x1b[?1049h\x1b[0;32mx\x1b[?1049ly"
+    );
+    //Each screen has its own saved pen.
+    assert_eq!(
+        optimized("\x1b[31m\x1b7\x1b[?1049h\x1b[32m\x1b7\x1b[0m\x1b8a\x1b[?1049lb"),
+        "\x1b[31m\x1b7\x1b[?1049h\x1b[32m\x1b7\x1b8a\x1b[?1049lb"
+    );
+    assert_eq!(
+        optimized("\x1b[44m\x1b[?1049h\x1b[0m\x1b[?1047lx"),
+        "\x1b[44m\x1b[?1049h\x1b[m\x1b[?1047lx"
+    );
+}
+
+#[test]
+fn test_splits_long_changes() {
+    assert_eq!(
+        check("\x1b[1m\x1b[38;2;1;2;3mX"),
+        "\x1b[1m\x1b[38;2;1;2;3mX"
+    );
+    let output = check("\x1b[1m\x1b[3m\x1b[4m\x1b[38;5;9m\x1b[48;2;1;2;3mX\x1b[0m");
+    assert!(output
+        .ansi_parse()
+        .all(|part| !matches!(part, Output::TextBlock(text) if text.contains('\u{1b}'))));
+}
+
+#[test]
+fn test_draws_the_same_screen() {
+    let output = check_screen(
+        "\x1b[2;3r\x1b[3;1H\x1b[31ma\nb\nc\x1b[0m\x1b[r\x1b[H\x1b[1mtop\x1b[A\x1b[B\x1b[C\x1b[D",
+        4,
+        10,
+    );
+    assert_eq!(
+        output,
+        "\x1b[2;3r\x1b[3;1H\x1b[31ma\nb\nc\x1b[m\x1b[r\x1b[H\x1b[1mtop\x1b[A\x1b[B\x1b[C\x1b[D"
+    );
+    check_screen("\x1b[44m\x1b[2J\x1b[1;1Hx\x1b[0m\x1b[K\x1b[5;3Hy", 6, 8);
+}
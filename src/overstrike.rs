@@ -0,0 +1,179 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+#[cfg(test)]
+mod tests;
+
+use crate::enums::Output;
+use crate::style::{Style, StyledIterator};
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::string::String;
+use std::vec::Vec;
+
+const BACKSPACE: char = '\u{8}';
+
+//A run being collected, borrowed for as long as it is one unbroken piece of the input.
+#[derive(Debug)]
+enum Run {
+    Borrowed(usize, usize),
+    Owned(String),
+}
+
+//A character as shown, along with where it is in the input unless something was struck over
+//it.
+#[derive(Debug)]
+struct Cell {
+    shown: char,
+    style: Style,
+    source: Option<(usize, usize)>,
+}
+
+impl Cell {
+    //Strikes `c` over the character, a different character simply replacing it as on a
+    //terminal.
+    fn strike(&mut self, base: Style, c: char) {
+        if c == self.shown {
+            self.style.bold = true;
+        } else if self.shown == '_' {
+            self.style.underline = true;
+            self.shown = c;
+        } else if c == '_' {
+            self.style.underline = true;
+        } else {
+            self.shown = c;
+            self.style = base;
+        }
+        self.source = None;
+    }
+}
+
+//Plays one text block back with backspace moving the cursor one character to the left, then
+//splits the result into styled runs.
+fn decode<'a>(base: Style, text: &'a str, out: &mut VecDeque<(Style, Cow<'a, str>)>) {
+    let mut cells: Vec<Cell> = Vec::new();
+    //A backspace does not go back past the start of the line.
+    let mut line_start = 0;
+    let mut cursor = 0;
+
+    for (start, c) in text.char_indices() {
+        match c {
+            BACKSPACE => cursor = cursor.max(line_start + 1) - 1,
+            '\n' => {
+                cells.push(Cell {
+                    shown: c,
+                    style: base,
+                    source: Some((start, start + 1)),
+                });
+                line_start = cells.len();
+                cursor = line_start;
+            }
+            c if cursor < cells.len() => {
+                cells[cursor].strike(base, c);
+                cursor += 1;
+            }
+            c => {
+                cells.push(Cell {
+                    shown: c,
+                    style: base,
+                    source: Some((start, start + c.len_utf8())),
+                });
+                cursor += 1;
+            }
+        }
+    }
+
+    let mut run: Option<(Style, Run)> = None;
+    let flush = |run: Option<(Style, Run)>, out: &mut VecDeque<_>| {
+        if let Some((style, run)) = run {
+            out.push_back(match run {
+                Run::Borrowed(start, end) => (style, Cow::Borrowed(&text[start..end])),
+                Run::Owned(s) => (style, Cow::Owned(s)),
+            });
+        }
+    };
+
+    for cell in cells {
+        match &mut run {
+            Some((run_style, Run::Borrowed(run_start, end))) if *run_style == cell.style => {
+                match cell.source {
+                    Some((start, source_end)) if start == *end => *end = source_end,
+                    _ => {
+                        let mut s = String::from(&text[*run_start..*end]);
+                        s.push(cell.shown);
+                        run = Some((cell.style, Run::Owned(s)));
+                    }
+                }
+            }
+            Some((run_style, Run::Owned(s))) if *run_style == cell.style => s.push(cell.shown),
+            _ => {
+                let next = match cell.source {
+                    Some((start, end)) => Run::Borrowed(start, end),
+                    None => Run::Owned(cell.shown.to_string()),
+                };
+                flush(run.replace((cell.style, next)), out);
+            }
+        }
+    }
+
+    flush(run, out);
+}
+
+///Turns the backspace overstrikes that `man` and `less` use into styles: `X\bX` is bold and
+///`_\bX` is underlined, over whatever style the text already had.
+///
+///A backspace moves back one character within the line as on a terminal, so `ab\b\bc` shows
+///`cb`, and anything other than the overstrike patterns replaces what it is struck over.
+///
+///Runs without overstrikes are borrowed from the input, decoded runs are owned.
+#[derive(Debug)]
+pub struct OverstrikeIterator<'a, I> {
+    inner: I,
+    pending: VecDeque<(Style, Cow<'a, str>)>,
+}
+
+impl<'a, I> OverstrikeIterator<'a, I>
+where
+    I: Iterator<Item = (Style, &'a str)>,
+{
+    pub fn new(inner: I) -> Self {
+        OverstrikeIterator {
+            inner,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl<'a, I> Iterator for OverstrikeIterator<'a, I>
+where
+    I: Iterator<Item = (Style, &'a str)>,
+{
+    type Item = (Style, Cow<'a, str>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(run) = self.pending.pop_front() {
+                return Some(run);
+            }
+
+            let (style, text) = self.inner.next()?;
+            if text.contains(BACKSPACE) {
+                decode(style, text, &mut self.pending);
+            } else {
+                return Some((style, Cow::Borrowed(text)));
+            }
+        }
+    }
+}
+
+impl<'a, I> StyledIterator<'a, I>
+where
+    I: Iterator<Item = Output<'a>>,
+{
+    ///Decodes overstrikes in the styled text, see [`OverstrikeIterator`].
+    pub fn overstrike(self) -> OverstrikeIterator<'a, Self> {
+        OverstrikeIterator::new(self)
+    }
+}
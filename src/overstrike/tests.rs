@@ -0,0 +1,115 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+use super::*;
+use crate::traits::AnsiParser;
+
+use std::vec::Vec;
+
+fn runs(input: &str) -> Vec<(Style, Cow<'_, str>)> {
+    input.ansi_parse().styled().overstrike().collect()
+}
+
+fn bold() -> Style {
+    Style {
+        bold: true,
+        ..Style::default()
+    }
+}
+
+fn underline() -> Style {
+    Style {
+        underline: true,
+        ..Style::default()
+    }
+}
+
+#[test]
+fn test_plain_text_is_borrowed() {
+    let out = runs("no overstrikes here");
+    assert_eq!(out, vec![(Style::default(), "no overstrikes here".into())]);
+    assert!(matches!(out[0].1, Cow::Borrowed(_)));
+}
+
+#[test]
+fn test_bold() {
+    assert_eq!(
+        runs("N\u{8}NA\u{8}AM\u{8}ME\u{8}E\n"),
+        vec![(bold(), "NAME".into()), (Style::default(), "\n".into())]
+    );
+}
+
+#[test]
+fn test_underline() {
+    assert_eq!(
+        runs("ls [_\u{8}f_\u{8}i_\u{8}l_\u{8}e]"),
+        vec![
+            (Style::default(), "ls [".into()),
+            (underline(), "file".into()),
+            (Style::default(), "]".into()),
+        ]
+    );
+    //Some formatters put the underscore second.
+    assert_eq!(runs("a\u{8}_"), vec![(underline(), "a".into())]);
+}
+
+#[test]
+fn test_bold_underscore() {
+    assert_eq!(runs("_\u{8}_"), vec![(bold(), "_".into())]);
+}
+
+#[test]
+fn test_bold_and_underline() {
+    let both = Style {
+        bold: true,
+        underline: true,
+        ..Style::default()
+    };
+    assert_eq!(runs("_\u{8}x\u{8}x"), vec![(both, "x".into())]);
+}
+
+#[test]
+fn test_multibyte() {
+    assert_eq!(runs("\u{e9}\u{8}\u{e9}"), vec![(bold(), "\u{e9}".into())]);
+}
+
+#[test]
+fn test_overwrite_replaces() {
+    assert_eq!(runs("a\u{8}b"), vec![(Style::default(), "b".into())]);
+}
+
+#[test]
+fn test_backspace_moves_the_cursor() {
+    //At the start of a line there is nothing to move back to.
+    assert_eq!(runs("\u{8}ab\u{8}"), vec![(Style::default(), "ab".into())]);
+    assert_eq!(runs("ab\u{8}\u{8}c"), vec![(Style::default(), "cb".into())]);
+    assert_eq!(runs("ab\u{8}\u{8}ab"), vec![(bold(), "ab".into())]);
+    assert_eq!(
+        runs("a\n\u{8}b\u{8}b"),
+        vec![(Style::default(), "a\n".into()), (bold(), "b".into())]
+    );
+    //What is not struck over is still borrowed.
+    assert!(matches!(
+        runs("plain x\u{8}x")[0].1,
+        Cow::Borrowed("plain ")
+    ));
+}
+
+#[test]
+fn test_combines_with_sgr() {
+    let red = Style {
+        fg: Some(crate::style::Color::Red),
+        ..Style::default()
+    };
+    let red_bold = Style { bold: true, ..red };
+    //Bold from either source comes out as the same style.
+    assert_eq!(
+        runs("\x1b[31mx\u{8}xy\x1b[1mz\x1b[0m"),
+        vec![
+            (red_bold, "x".into()),
+            (red, "y".into()),
+            (red_bold, "z".into())
+        ]
+    );
+}
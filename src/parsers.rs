@@ -5,7 +5,7 @@
 #[cfg(test)]
 mod tests;
 
-use crate::AnsiSequence;
+use crate::{AnsiSequence, Charset};
 
 use core::convert::TryInto;
 use heapless::Vec;
@@ -194,6 +194,32 @@ named!(
     )
 );
 
+named!(
+    graphics_mode6<&str, AnsiSequence>,
+    do_parse!(
+        tag!("[")       >>
+        val1: parse_int >>
+        tag!(";")       >>
+        val2: parse_int >>
+        tag!(";")       >>
+        val3: parse_int >>
+        tag!(";")       >>
+        val4: parse_int >>
+        tag!("m")       >>
+        val1: expr_res!(val1.try_into()) >>
+        val2: expr_res!(val2.try_into()) >>
+        val3: expr_res!(val3.try_into()) >>
+        val4: expr_res!(val4.try_into()) >>
+        conv: expr_res!(Vec::from_slice(&[
+            val1,
+            val2,
+            val3,
+            val4,
+        ])) >>
+        (AnsiSequence::SetGraphicsMode(conv))
+    )
+);
+
 named!(
     graphics_mode<&str, AnsiSequence>,
     alt!(
@@ -202,6 +228,7 @@ named!(
         | graphics_mode3
         | graphics_mode4
         | graphics_mode5
+        | graphics_mode6
     )
 );
 
@@ -239,10 +266,69 @@ named!(
     )
 );
 
+named!(
+    push_keyboard_flags<&str, AnsiSequence>,
+    do_parse!(
+        tag!("[>")                        >>
+        flags: parse_int                  >>
+        conv: expr_res!(flags.try_into()) >>
+        tag!("u")                         >>
+        (AnsiSequence::PushKeyboardFlags(conv))
+    )
+);
+
+named!(
+    pop_keyboard_flags<&str, AnsiSequence>,
+    do_parse!(
+        tag!("[<")                >>
+        amt: parse_def_cursor_int >>
+        tag!("u")                 >>
+        (AnsiSequence::PopKeyboardFlags(amt))
+    )
+);
+
+//XTMODKEYS shares the `m` final byte with SGR, the private marker is what tells them apart.
+named!(
+    set_key_modifier_option<&str, AnsiSequence>,
+    do_parse!(
+        tag!("[>")                      >>
+        res: parse_int                  >>
+        tag!(";")                       >>
+        val: parse_int                  >>
+        tag!("m")                       >>
+        res: expr_res!(res.try_into())  >>
+        val: expr_res!(val.try_into())  >>
+        (AnsiSequence::SetKeyModifierOption(res, val))
+    )
+);
+
+named!(
+    reset_key_modifier_option<&str, AnsiSequence>,
+    do_parse!(
+        tag!("[>")                      >>
+        res: parse_int                  >>
+        tag!("m")                       >>
+        res: expr_res!(res.try_into())  >>
+        (AnsiSequence::ResetKeyModifierOption(res))
+    )
+);
+
+named!(
+    query_key_modifier_option<&str, AnsiSequence>,
+    do_parse!(
+        tag!("[?")                      >>
+        res: parse_int                  >>
+        tag!("m")                       >>
+        res: expr_res!(res.try_into())  >>
+        (AnsiSequence::QueryKeyModifierOption(res))
+    )
+);
+
 tag_parser!(cursor_save, "[s", AnsiSequence::CursorSave);
 tag_parser!(cursor_restore, "[u", AnsiSequence::CursorRestore);
 tag_parser!(erase_display, "[2J", AnsiSequence::EraseDisplay);
 tag_parser!(erase_line, "[K", AnsiSequence::EraseLine);
+tag_parser!(erase_scrollback, "[3J", AnsiSequence::EraseScrollback);
 tag_parser!(hide_cursor, "[?25l", AnsiSequence::HideCursor);
 tag_parser!(show_cursor, "[?25h", AnsiSequence::ShowCursor);
 tag_parser!(cursor_to_app, "[?1h", AnsiSequence::CursorToApp);
@@ -264,6 +350,93 @@ tag_parser!(set_origin_abs, "[?6l", AnsiSequence::SetOriginAbsolute);
 tag_parser!(reset_auto_wrap, "[?7l", AnsiSequence::ResetAutoWrap);
 tag_parser!(reset_auto_repeat, "[?8l", AnsiSequence::ResetAutoRepeat);
 tag_parser!(reset_interlacing, "[?9l", AnsiSequence::ResetInterlacing);
+tag_parser!(query_kb_flags, "[?u", AnsiSequence::QueryKeyboardFlags);
+tag_parser!(set_paste_mode, "[?2004h", AnsiSequence::SetBracketedPaste);
+tag_parser!(
+    reset_paste_mode,
+    "[?2004l",
+    AnsiSequence::ResetBracketedPaste
+);
+tag_parser!(paste_start, "[200~", AnsiSequence::PasteStart);
+tag_parser!(paste_end, "[201~", AnsiSequence::PasteEnd);
+tag_parser!(set_focus_report, "[?1004h", AnsiSequence::SetFocusReporting);
+tag_parser!(
+    reset_focus_report,
+    "[?1004l",
+    AnsiSequence::ResetFocusReporting
+);
+tag_parser!(
+    set_mouse_tracking,
+    "[?1000h",
+    AnsiSequence::SetMouseTracking
+);
+tag_parser!(
+    reset_mouse_tracking,
+    "[?1000l",
+    AnsiSequence::ResetMouseTracking
+);
+tag_parser!(
+    set_button_event_tracking,
+    "[?1002h",
+    AnsiSequence::SetButtonEventTracking
+);
+tag_parser!(
+    reset_button_event_tracking,
+    "[?1002l",
+    AnsiSequence::ResetButtonEventTracking
+);
+tag_parser!(
+    set_any_event_tracking,
+    "[?1003h",
+    AnsiSequence::SetAnyEventTracking
+);
+tag_parser!(
+    reset_any_event_tracking,
+    "[?1003l",
+    AnsiSequence::ResetAnyEventTracking
+);
+tag_parser!(set_sgr_mouse, "[?1006h", AnsiSequence::SetSgrMouse);
+tag_parser!(reset_sgr_mouse, "[?1006l", AnsiSequence::ResetSgrMouse);
+tag_parser!(focus_in, "[I", AnsiSequence::FocusIn);
+tag_parser!(
+    set_sync_update,
+    "[?2026h",
+    AnsiSequence::SetSynchronizedUpdate
+);
+tag_parser!(
+    reset_sync_update,
+    "[?2026l",
+    AnsiSequence::ResetSynchronizedUpdate
+);
+tag_parser!(focus_out, "[O", AnsiSequence::FocusOut);
+tag_parser!(set_alt_screen, "[?47h", AnsiSequence::SetAlternateScreen);
+tag_parser!(
+    reset_alt_screen,
+    "[?47l",
+    AnsiSequence::ResetAlternateScreen
+);
+tag_parser!(
+    set_alt_screen_clear,
+    "[?1047h",
+    AnsiSequence::SetAlternateScreenClear
+);
+tag_parser!(
+    reset_alt_screen_clear,
+    "[?1047l",
+    AnsiSequence::ResetAlternateScreenClear
+);
+tag_parser!(
+    set_alt_screen_save,
+    "[?1049h",
+    AnsiSequence::SetAlternateScreenSaveCursor
+);
+tag_parser!(
+    reset_alt_screen_save,
+    "[?1049l",
+    AnsiSequence::ResetAlternateScreenSaveCursor
+);
+tag_parser!(save_cursor, "7", AnsiSequence::SaveCursor);
+tag_parser!(restore_cursor, "8", AnsiSequence::RestoreCursor);
 
 tag_parser!(set_alternate_keypad, "=", AnsiSequence::SetAlternateKeypad);
 tag_parser!(set_numeric_keypad, ">", AnsiSequence::SetNumericKeypad);
@@ -277,6 +450,47 @@ tag_parser!(set_g0_alternate, "(1", AnsiSequence::SetG0AlternateChar);
 tag_parser!(set_g1_alternate, ")1", AnsiSequence::SetG1AlternateChar);
 tag_parser!(set_g0_graph, "(2", AnsiSequence::SetG0AltAndSpecialGraph);
 tag_parser!(set_g1_graph, ")2", AnsiSequence::SetG1AltAndSpecialGraph);
+tag_parser!(
+    set_g0_supplemental,
+    "(<",
+    AnsiSequence::SetG0DecSupplemental
+);
+tag_parser!(
+    set_g1_supplemental,
+    ")<",
+    AnsiSequence::SetG1DecSupplemental
+);
+
+named!(
+    charset<&str, Charset>,
+    alt!(
+          map!(tag!("B"), |_| Charset::Ascii)
+        | map!(tag!("A"), |_| Charset::Uk)
+        | map!(tag!("0"), |_| Charset::DecSpecialGraphics)
+        | map!(tag!("<"), |_| Charset::DecSupplemental)
+    )
+);
+
+named!(
+    set_g2_charset<&str, AnsiSequence>,
+    do_parse!(
+        tag!("*")    >>
+        set: charset >>
+        (AnsiSequence::SetG2Charset(set))
+    )
+);
+
+named!(
+    set_g3_charset<&str, AnsiSequence>,
+    do_parse!(
+        tag!("+")    >>
+        set: charset >>
+        (AnsiSequence::SetG3Charset(set))
+    )
+);
+
+tag_parser!(lock_shift_g2, "n", AnsiSequence::LockShiftG2);
+tag_parser!(lock_shift_g3, "o", AnsiSequence::LockShiftG3);
 tag_parser!(set_single_shift2, "N", AnsiSequence::SetSingleShift2);
 tag_parser!(set_single_shift3, "O", AnsiSequence::SetSingleShift3);
 
@@ -293,6 +507,7 @@ named!(
         | cursor_restore
         | erase_display
         | erase_line
+        | erase_scrollback
         | graphics_mode
         | set_mode
         | reset_mode
@@ -330,8 +545,46 @@ named!(
         | set_g1_alternate
         | set_g0_graph
         | set_g1_graph
+        | set_g0_supplemental
+        | set_g1_supplemental
+        | set_g2_charset
+        | set_g3_charset
+        | lock_shift_g2
+        | lock_shift_g3
+        | focus_in
+        | focus_out
         | set_single_shift2
         | set_single_shift3
+        | push_keyboard_flags
+        | pop_keyboard_flags
+        | query_kb_flags
+        | set_key_modifier_option
+        | reset_key_modifier_option
+        | query_key_modifier_option
+        | set_paste_mode
+        | reset_paste_mode
+        | paste_start
+        | paste_end
+        | set_focus_report
+        | reset_focus_report
+        | set_mouse_tracking
+        | reset_mouse_tracking
+        | set_button_event_tracking
+        | reset_button_event_tracking
+        | set_any_event_tracking
+        | reset_any_event_tracking
+        | set_sgr_mouse
+        | reset_sgr_mouse
+        | set_sync_update
+        | reset_sync_update
+        | set_alt_screen
+        | reset_alt_screen
+        | set_alt_screen_clear
+        | reset_alt_screen_clear
+        | set_alt_screen_save
+        | reset_alt_screen_save
+        | save_cursor
+        | restore_cursor
     )
 );
 
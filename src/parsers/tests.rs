@@ -55,16 +55,23 @@ test_def_val_parser!(cursor_up, "\u{1b}[5A");
 test_def_val_parser!(cursor_down, "\u{1b}[5B");
 test_def_val_parser!(cursor_forward, "\u{1b}[5C");
 test_def_val_parser!(cursor_backward, "\u{1b}[5D");
+test_parser!(cursor_home, "\u{1b}[H");
+test_parser!(cursor_up_one, "\u{1b}[A");
+test_parser!(cursor_backward_one, "\u{1b}[D");
+test_parser!(set_top_and_bottom, "\u{1b}[2;3r");
 test_parser!(cursor_save, "\u{1b}[s");
 test_parser!(cursor_restore, "\u{1b}[u");
 
 test_parser!(erase_display, "\u{1b}[2J");
+test_parser!(erase_scrollback, "\u{1b}[3J");
 test_parser!(erase_line, "\u{1b}[K");
 
 test_parser!(set_video_mode_a, "\u{1b}[4m");
 test_parser!(set_video_mode_b, "\u{1b}[4;42m");
 test_parser!(set_video_mode_c, "\u{1b}[4;31;42m");
 test_parser!(set_video_mode_d, "\u{1b}[4;31;42;42;42m");
+test_parser!(set_video_mode_e, "\u{1b}[1;38;5;208m");
+test_parser!(set_video_mode_reset, "\u{1b}[m");
 
 test_parser!(reset_mode, "\u{1b}[=13l");
 test_parser!(set_mode, "\u{1b}[=7h");
@@ -106,9 +113,87 @@ test_parser!(set_g0_alternate, "\u{1b}(1");
 test_parser!(set_g1_alternate, "\u{1b})1");
 test_parser!(set_g0_graph, "\u{1b}(2");
 test_parser!(set_g1_graph, "\u{1b})2");
+test_parser!(set_g0_supplemental, "\u{1b}(<");
+test_parser!(set_g1_supplemental, "\u{1b})<");
+test_parser!(set_g2_uk, "\u{1b}*A");
+test_parser!(set_g2_special, "\u{1b}*0");
+test_parser!(set_g3_us, "\u{1b}+B");
+test_parser!(set_g3_supplemental, "\u{1b}+<");
+test_parser!(lock_shift_g2, "\u{1b}n");
+test_parser!(lock_shift_g3, "\u{1b}o");
 test_parser!(set_single_shift2, "\u{1b}N");
 test_parser!(set_single_shift3, "\u{1b}O");
 
+test_parser!(push_keyboard_flags, "\u{1b}[>1u");
+test_parser!(pop_keyboard_flags, "\u{1b}[<2u");
+test_def_val_parser!(pop_keyboard_flags_default, "\u{1b}[<u");
+test_parser!(query_keyboard_flags, "\u{1b}[?u");
+
+test_parser!(set_modify_other_keys, "\u{1b}[>4;2m");
+test_parser!(reset_modify_other_keys, "\u{1b}[>4m");
+test_parser!(query_modify_other_keys, "\u{1b}[?4m");
+
+test_parser!(set_bracketed_paste, "\u{1b}[?2004h");
+test_parser!(reset_bracketed_paste, "\u{1b}[?2004l");
+test_parser!(paste_start, "\u{1b}[200~");
+test_parser!(paste_end, "\u{1b}[201~");
+
+test_parser!(set_focus_reporting, "\u{1b}[?1004h");
+test_parser!(reset_focus_reporting, "\u{1b}[?1004l");
+test_parser!(set_mouse_tracking, "\u{1b}[?1000h");
+test_parser!(reset_mouse_tracking, "\u{1b}[?1000l");
+test_parser!(set_button_event_tracking, "\u{1b}[?1002h");
+test_parser!(reset_button_event_tracking, "\u{1b}[?1002l");
+test_parser!(set_any_event_tracking, "\u{1b}[?1003h");
+test_parser!(reset_any_event_tracking, "\u{1b}[?1003l");
+test_parser!(set_sgr_mouse, "\u{1b}[?1006h");
+test_parser!(reset_sgr_mouse, "\u{1b}[?1006l");
+test_parser!(focus_in, "\u{1b}[I");
+test_parser!(focus_out, "\u{1b}[O");
+
+test_parser!(set_sync_update, "\u{1b}[?2026h");
+test_parser!(reset_sync_update, "\u{1b}[?2026l");
+
+test_parser!(set_alt_screen, "\u{1b}[?47h");
+test_parser!(reset_alt_screen, "\u{1b}[?47l");
+test_parser!(set_alt_screen_clear, "\u{1b}[?1047h");
+test_parser!(reset_alt_screen_clear, "\u{1b}[?1047l");
+test_parser!(set_alt_screen_save, "\u{1b}[?1049h");
+test_parser!(reset_alt_screen_save, "\u{1b}[?1049l");
+test_parser!(save_cursor, "\u{1b}7");
+test_parser!(restore_cursor, "\u{1b}8");
+
+#[test]
+fn test_modify_other_keys_is_not_sgr() {
+    let parts: Vec<_> = "\x1b[>4;2m\x1b[4;2mx\x1b[>4m".ansi_parse().collect();
+    assert_eq!(
+        parts,
+        vec![
+            Output::Escape(AnsiSequence::SetKeyModifierOption(4, 2)),
+            Output::Escape(AnsiSequence::SetGraphicsMode(
+                heapless::Vec::from_slice(&[4, 2]).unwrap()
+            )),
+            Output::TextBlock("x"),
+            Output::Escape(AnsiSequence::ResetKeyModifierOption(4)),
+        ]
+    );
+}
+
+#[test]
+fn test_focus_out_is_not_single_shift3() {
+    let parts: Vec<_> = "\x1b[OA\x1bOA\x1b[I".ansi_parse().collect();
+    assert_eq!(
+        parts,
+        vec![
+            Output::Escape(AnsiSequence::FocusOut),
+            Output::TextBlock("A"),
+            Output::Escape(AnsiSequence::SetSingleShift3),
+            Output::TextBlock("A"),
+            Output::Escape(AnsiSequence::FocusIn),
+        ]
+    );
+}
+
 #[test]
 fn test_parser_iterator() {
     let count = "\x1b[=25l\x1b[=7l\x1b[0m\x1b[36m\x1b[1m-`"
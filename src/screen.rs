@@ -0,0 +1,809 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+mod redraw;
+mod snapshot;
+#[cfg(test)]
+mod tests;
+
+use crate::cell::{self, next_char, Cell, TAB_WIDTH};
+use crate::charset::CharsetState;
+use crate::enums::{AnsiSequence, Output};
+use crate::strip::unfinished_escape;
+use crate::style::{Style, StyleState};
+use crate::traits::AnsiParser;
+use redraw::Frame;
+pub use snapshot::{ExpectedSnapshot, Snapshot, SnapshotError};
+
+use core::fmt::{Result as DisplayResult, Write};
+use std::collections::VecDeque;
+use std::string::String;
+use std::vec::Vec;
+use unicode_width::UnicodeWidthChar;
+
+const DEFAULT_SCROLLBACK: usize = 1000;
+
+//An unfinished sequence longer than this is taken as it is instead of being held back, so that
+//an unterminated string can not take up memory without bound.
+const MAX_PARTIAL: usize = 4096;
+
+//What DECSC saves, which xterm also uses for `CursorSave`.
+#[derive(Debug, Default, Clone, Copy)]
+struct SavedCursor {
+    row: usize,
+    col: usize,
+    wrap_pending: bool,
+    pen: StyleState,
+    origin: bool,
+    charsets: CharsetState,
+}
+
+//A row of cells, and whether autowrap carried on from it onto the next row.
+#[derive(Debug, Clone)]
+struct Line {
+    cells: Vec<Cell>,
+    wrapped: bool,
+}
+
+impl Line {
+    fn new(cols: usize, blank: Cell) -> Self {
+        Line {
+            cells: vec![blank; cols],
+            wrapped: false,
+        }
+    }
+
+    fn is_blank(&self) -> bool {
+        !self.wrapped && self.cells.iter().all(Cell::is_blank)
+    }
+
+    //Cuts or pads the line to `cols` without reflowing it.
+    fn resize(&mut self, cols: usize) {
+        self.cells.resize(cols, Cell::default());
+        if self.cells.last().is_some_and(|cell| cell.width() == 2) {
+            self.cells[cols - 1] = Cell::default();
+        }
+        self.wrapped = false;
+    }
+}
+
+//Wraps the cells of one logical line onto rows of `cols`, appending them to `out`. Returns
+//where every cell went as a row in `out` and a column, plus where the next one would go.
+fn wrap_line(cells: &[Cell], cols: usize, out: &mut Vec<Line>) -> Vec<(usize, usize)> {
+    let mut places = Vec::with_capacity(cells.len() + 1);
+    let mut row: Vec<Cell> = Vec::with_capacity(cols);
+
+    let mut i = 0;
+    while i < cells.len() {
+        let cell = cells[i];
+        let spacer = cells
+            .get(i + 1)
+            .filter(|next| cell.width() == 2 && next.is_spacer());
+        let width = cell.width().max(1);
+
+        //A wide character that does not fit leaves the last column blank, as when writing.
+        if row.len() + width > cols && width <= cols {
+            row.resize(cols, Cell::default());
+            out.push(Line {
+                cells: core::mem::replace(&mut row, Vec::with_capacity(cols)),
+                wrapped: true,
+            });
+        }
+
+        let place = (out.len(), row.len().min(cols - 1));
+        places.push(place);
+        if width <= cols {
+            row.push(cell);
+            if width == 2 {
+                row.push(spacer.copied().unwrap_or_else(|| Cell::spacer(cell.style)));
+            }
+        }
+        if spacer.is_some() {
+            places.push((place.0, (place.1 + 1).min(cols - 1)));
+            i += 1;
+        }
+        i += 1;
+    }
+
+    places.push((out.len(), row.len()));
+    row.resize(cols, Cell::default());
+    out.push(Line {
+        cells: row,
+        wrapped: false,
+    });
+    places
+}
+
+//Joins soft-wrapped lines back together and wraps them again at `cols`. Each mark is a line
+//index and a column, and is moved along with the character it is on.
+fn reflow(lines: Vec<Line>, cols: usize, marks: &mut [(usize, usize)]) -> Vec<Line> {
+    let mut out = Vec::with_capacity(lines.len());
+    let mut logical: Vec<Cell> = Vec::new();
+    //The marks in the current logical line, with their offsets in it.
+    let mut offsets: Vec<(usize, usize)> = Vec::new();
+
+    let count = lines.len();
+    for (i, line) in lines.into_iter().enumerate() {
+        //The blank left behind by a wide character that did not fit is not part of the text.
+        let wide_first = line.cells.first().is_some_and(|cell| cell.width() == 2);
+        if wide_first && logical.last().is_some_and(Cell::is_blank) {
+            logical.pop();
+        }
+
+        for (mark, &(row, col)) in marks.iter().enumerate() {
+            if row == i {
+                offsets.push((mark, logical.len() + col));
+            }
+        }
+
+        logical.extend(line.cells);
+        if line.wrapped && i + 1 < count {
+            continue;
+        }
+
+        let end = logical
+            .iter()
+            .rposition(|cell| !cell.is_blank())
+            .map_or(0, |i| i + 1);
+        logical.truncate(end);
+
+        let places = wrap_line(&logical, cols, &mut out);
+        for (mark, offset) in offsets.drain(..) {
+            marks[mark] = match places.get(offset) {
+                Some(&place) if offset < logical.len() => place,
+                //Past the end of the text the mark keeps its distance from it, within the row.
+                _ => {
+                    let (row, col) = places[logical.len()];
+                    (row, (col + offset - logical.len()).min(cols - 1))
+                }
+            };
+        }
+        logical.clear();
+    }
+    out
+}
+
+//The text of a line without trailing blanks.
+fn line_text(cells: &[Cell]) -> String {
+    let mut text = String::new();
+    for cell in cells {
+        cell.push_to(&mut text);
+    }
+    text.truncate(text.trim_end_matches(' ').len());
+    text
+}
+
+//Like `line_text`, with SGR sequences for the styles, starting and ending with the default
+//style. Blanks are only trailing when they have no background either.
+fn write_styled_line<W: Write>(cells: &[Cell], writer: &mut W) -> DisplayResult {
+    let end = cells
+        .iter()
+        .rposition(|cell| !cell.is_blank())
+        .map_or(0, |i| i + 1);
+
+    let mut pen = Style::default();
+    let mut text = String::new();
+    for cell in &cells[..end] {
+        pen.write_diff(&cell.style, writer)?;
+        pen = cell.style;
+        text.clear();
+        cell.push_to(&mut text);
+        writer.write_str(&text)?;
+    }
+    pen.write_diff(&Style::default(), writer)
+}
+
+///A terminal screen of a fixed number of rows and columns that interprets an [`Output`]
+///stream, for checking what a program would leave on a real terminal.
+///
+///Rows and columns are counted from 0 here, unlike in the escape sequences themselves.
+#[derive(Debug, Clone)]
+pub struct Screen {
+    rows: usize,
+    cols: usize,
+    grid: Vec<Line>,
+    //Lines scrolled off the top of the main screen, oldest first.
+    scrollback: VecDeque<Line>,
+    scrollback_limit: usize,
+    //The main screen while the alternate one is shown, and the other way round.
+    other_grid: Vec<Line>,
+    alternate: bool,
+    row: usize,
+    col: usize,
+    pen: StyleState,
+    charsets: CharsetState,
+    //Each screen has its own saved cursor.
+    saved: SavedCursor,
+    other_saved: SavedCursor,
+    //The last column was written with autowrap on, the next character goes on a new line.
+    wrap_pending: bool,
+    //The scroll margins set by `SetTopAndBottom`, inclusive.
+    top: usize,
+    bottom: usize,
+    cursor_visible: bool,
+    autowrap: bool,
+    origin: bool,
+    new_line_mode: bool,
+    reverse_video: bool,
+    //The frame a real terminal was last brought to by `redraw`.
+    front: Frame,
+    //The start of an escape sequence that the last input ended in the middle of.
+    partial: String,
+}
+
+impl Screen {
+    ///An empty screen, with at least one row and column.
+    pub fn new(rows: usize, cols: usize) -> Self {
+        let rows = rows.max(1);
+        let cols = cols.max(1);
+        Screen {
+            rows,
+            cols,
+            grid: vec![Line::new(cols, Cell::default()); rows],
+            other_grid: vec![Line::new(cols, Cell::default()); rows],
+            scrollback: VecDeque::new(),
+            scrollback_limit: DEFAULT_SCROLLBACK,
+            alternate: false,
+            row: 0,
+            col: 0,
+            pen: StyleState::new(),
+            charsets: CharsetState::new(),
+            saved: SavedCursor::default(),
+            other_saved: SavedCursor::default(),
+            wrap_pending: false,
+            top: 0,
+            bottom: rows - 1,
+            cursor_visible: true,
+            autowrap: true,
+            origin: false,
+            new_line_mode: false,
+            reverse_video: false,
+            front: Frame::new(rows, cols),
+            partial: String::new(),
+        }
+    }
+
+    ///Keeps at most `limit` lines that scrolled off the top, 1000 by default. With a limit of 0
+    ///they are dropped straight away.
+    pub fn with_scrollback(mut self, limit: usize) -> Self {
+        self.scrollback_limit = limit;
+        self.trim_scrollback();
+        self
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    ///The cursor as `(row, col)`, always on the screen.
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    ///Whether the last column was just written, so that the next character wraps.
+    pub fn wrap_pending(&self) -> bool {
+        self.wrap_pending
+    }
+
+    ///Whether the alternate screen is shown.
+    pub fn alternate_screen(&self) -> bool {
+        self.alternate
+    }
+
+    pub fn cursor_visible(&self) -> bool {
+        self.cursor_visible
+    }
+
+    pub fn reverse_video(&self) -> bool {
+        self.reverse_video
+    }
+
+    pub fn autowrap(&self) -> bool {
+        self.autowrap
+    }
+
+    ///Whether cursor addresses are relative to the scroll region.
+    pub fn origin_mode(&self) -> bool {
+        self.origin
+    }
+
+    ///The scroll margins as the first and last row of the region.
+    pub fn scroll_region(&self) -> (usize, usize) {
+        (self.top, self.bottom)
+    }
+
+    ///The style that text written now is drawn in.
+    pub fn pen(&self) -> Style {
+        self.pen.style()
+    }
+
+    ///The character sets text written now is translated with.
+    pub fn charsets(&self) -> &CharsetState {
+        &self.charsets
+    }
+
+    pub fn cell(&self, row: usize, col: usize) -> Option<&Cell> {
+        self.grid.get(row)?.cells.get(col)
+    }
+
+    pub fn row(&self, row: usize) -> Option<&[Cell]> {
+        self.grid.get(row).map(|line| line.cells.as_slice())
+    }
+
+    ///Every row from top to bottom.
+    pub fn cells(&self) -> impl Iterator<Item = &[Cell]> + '_ {
+        self.grid.iter().map(|line| line.cells.as_slice())
+    }
+
+    ///Whether `row` carries on in the next row because autowrap broke it there, rather than
+    ///ending with a newline.
+    pub fn is_wrapped(&self, row: usize) -> bool {
+        self.grid.get(row).is_some_and(|line| line.wrapped)
+    }
+
+    ///The text of `row` without trailing blanks.
+    pub fn row_text(&self, row: usize) -> Option<String> {
+        self.row(row).map(line_text)
+    }
+
+    ///The text on the screen, a line per row without trailing blanks.
+    pub fn text(&self) -> String {
+        let rows: Vec<String> = (0..self.rows)
+            .filter_map(|row| self.row_text(row))
+            .collect();
+        rows.join("\n")
+    }
+
+    ///The lines that scrolled off the top, oldest first. The alternate screen does not add
+    ///to them, nor does a scroll region that does not start at the top.
+    pub fn scrollback(&self) -> impl Iterator<Item = &[Cell]> + '_ {
+        self.scrollback.iter().map(|line| line.cells.as_slice())
+    }
+
+    ///The scrollback followed by the rows on the screen, which are the last
+    ///[`rows`](Screen::rows) lines.
+    pub fn history(&self) -> impl Iterator<Item = &[Cell]> + '_ {
+        self.scrollback().chain(self.cells())
+    }
+
+    ///The text of the whole [`history`](Screen::history), a line per row without trailing
+    ///blanks.
+    pub fn history_text(&self) -> String {
+        let lines: Vec<String> = self.history().map(line_text).collect();
+        lines.join("\n")
+    }
+
+    ///Like [`history_text`](Screen::history_text), with SGR sequences for the styles. Each
+    ///line starts and ends with the default style.
+    pub fn history_styled_text(&self) -> String {
+        let mut text = String::new();
+        for (i, line) in self.history().enumerate() {
+            if i > 0 {
+                text.push('\n');
+            }
+            write_styled_line(line, &mut text).expect("writing to a String can not fail");
+        }
+        text
+    }
+
+    ///Finds `needle` in the text of the [`history`](Screen::history), as
+    ///`(line, col)` pairs of where each match starts.
+    pub fn search<'a>(&'a self, needle: &'a str) -> impl Iterator<Item = (usize, usize)> + 'a {
+        self.history().enumerate().flat_map(move |(line, cells)| {
+            //The column that every byte of the text comes from.
+            let mut text = String::new();
+            let mut cols = Vec::new();
+            for (col, cell) in cells.iter().enumerate() {
+                cell.push_to(&mut text);
+                cols.resize(text.len(), col);
+            }
+
+            let mut matches = Vec::new();
+            if !needle.is_empty() {
+                let mut from = 0;
+                while let Some(i) = text[from..].find(needle) {
+                    matches.push((line, cols[from + i]));
+                    from += i + needle.len();
+                }
+            }
+            matches
+        })
+    }
+
+    ///Changes the size of the screen, as when the terminal window is resized.
+    ///
+    ///The main screen and its scrollback are reflowed: lines that autowrap broke are joined
+    ///and wrapped again at the new width, while lines ending in a newline stay apart. The
+    ///cursor stays on the character it was on. Growing the screen brings back lines from the
+    ///scrollback, and shrinking it pushes lines into it. The alternate screen is cut or padded
+    ///instead, as programs using it redraw it. The scroll region is reset.
+    pub fn resize(&mut self, rows: usize, cols: usize) {
+        let rows = rows.max(1);
+        let cols = cols.max(1);
+
+        let main = if self.alternate {
+            core::mem::take(&mut self.other_grid)
+        } else {
+            core::mem::take(&mut self.grid)
+        };
+        let mut lines: Vec<Line> = core::mem::take(&mut self.scrollback).into();
+        let screen_start = lines.len();
+        lines.extend(main);
+
+        //Marks the first row of the screen and, on the main screen, the cursor.
+        let mut marks = vec![(screen_start, 0)];
+        if !self.alternate {
+            let col = self.col + self.wrap_pending as usize;
+            marks.push((screen_start + self.row, col));
+        }
+
+        //Blank rows below the cursor would only push text into the scrollback.
+        let keep = marks.last().map_or(0, |&(row, _)| row + 1);
+        while lines.len() > keep && lines.last().is_some_and(Line::is_blank) {
+            lines.pop();
+        }
+
+        let mut lines = reflow(lines, cols, &mut marks);
+
+        let grown = rows.saturating_sub(self.rows);
+        let mut start = lines
+            .len()
+            .saturating_sub(rows)
+            .max(marks[0].0.saturating_sub(grown));
+        if let Some(&(row, _)) = marks.get(1) {
+            start = start.min(row);
+        }
+        let mut grid = lines.split_off(start.min(lines.len()));
+        grid.truncate(rows);
+        grid.resize(rows, Line::new(cols, Cell::default()));
+        self.scrollback = lines.into();
+
+        let mut other = core::mem::take(if self.alternate {
+            &mut self.grid
+        } else {
+            &mut self.other_grid
+        });
+        other.truncate(rows);
+        other.resize(rows, Line::new(cols, Cell::default()));
+        for line in &mut other {
+            line.resize(cols);
+        }
+
+        if self.alternate {
+            self.grid = other;
+            self.other_grid = grid;
+        } else {
+            self.grid = grid;
+            self.other_grid = other;
+            let (row, col) = marks[1];
+            self.row = row - start;
+            self.col = col;
+        }
+
+        self.rows = rows;
+        self.cols = cols;
+        self.top = 0;
+        self.bottom = rows - 1;
+        self.move_to(self.row, self.col);
+        for saved in [&mut self.saved, &mut self.other_saved].iter_mut() {
+            saved.row = saved.row.min(rows - 1);
+            saved.col = saved.col.min(cols - 1);
+        }
+        self.trim_scrollback();
+        self.front = Frame::unknown(rows, cols);
+    }
+
+    ///Parses `input` and applies it to the screen.
+    ///
+    ///An escape sequence that `input` ends in the middle of is held back and finished by the
+    ///next call, so input can be fed in whatever pieces it is read in.
+    pub fn process(&mut self, input: &str) {
+        let joined;
+        let input = if self.partial.is_empty() {
+            input
+        } else {
+            self.partial.push_str(input);
+            joined = core::mem::take(&mut self.partial);
+            joined.as_str()
+        };
+
+        let end = match unfinished_escape(input) {
+            Some(start) if input.len() - start <= MAX_PARTIAL => start,
+            _ => input.len(),
+        };
+        for output in input[..end].ansi_parse() {
+            self.apply(&output);
+        }
+        self.partial.push_str(&input[end..]);
+    }
+
+    pub fn apply(&mut self, output: &Output<'_>) {
+        match output {
+            Output::TextBlock(text) => self.text_block(text),
+            Output::Escape(seq) => self.escape(seq),
+        }
+    }
+
+    fn text_block(&mut self, mut text: &str) {
+        while let Some(c) = next_char(&mut text) {
+            match c {
+                '\n' | '\u{b}' | '\u{c}' => {
+                    if self.new_line_mode {
+                        self.col = 0;
+                    }
+                    self.line_feed();
+                }
+                '\r' => self.move_to(self.row, 0),
+                '\u{8}' => self.move_to(self.row, self.col.saturating_sub(1)),
+                '\t' => self.move_to(self.row, (self.col / TAB_WIDTH + 1) * TAB_WIDTH),
+                c if self.charsets.shift(c) => {}
+                c if c.is_control() => {}
+                c => {
+                    let c = self.charsets.map(c);
+                    self.put(c);
+                }
+            }
+        }
+    }
+
+    fn put(&mut self, c: char) {
+        let width = match c.width() {
+            Some(0) | None => return self.combine(c),
+            Some(width) if width > self.cols => return,
+            Some(width) => width,
+        };
+
+        //A character that does not fit goes on the next line, a wide one leaving the last
+        //column as it was, as xterm does.
+        if self.wrap_pending || self.col + width > self.cols {
+            if self.autowrap {
+                self.grid[self.row].wrapped = true;
+                self.col = 0;
+                self.line_feed();
+            } else {
+                self.col = self.cols - width;
+            }
+        }
+
+        cell::put(
+            &mut self.grid[self.row].cells,
+            self.col,
+            c,
+            self.pen.style(),
+        );
+
+        //The cursor stays on the last column, and only moves on to the next line when there
+        //is something to write there.
+        if self.col + width < self.cols {
+            self.col += width;
+        } else {
+            self.col = self.cols - 1;
+            self.wrap_pending = self.autowrap;
+        }
+    }
+
+    //Combining marks go with the character before the cursor.
+    fn combine(&mut self, mark: char) {
+        let col = match self.col {
+            col if self.wrap_pending => col,
+            0 => return,
+            col => col - 1,
+        };
+        cell::combine(&mut self.grid[self.row].cells, col, mark);
+    }
+
+    //Moves down a row, scrolling the region when the cursor is on its bottom margin. Below
+    //the region the cursor stops at the last row instead.
+    fn line_feed(&mut self) {
+        self.wrap_pending = false;
+        if self.row == self.bottom {
+            self.scroll_up();
+        } else if self.row + 1 < self.rows {
+            self.row += 1;
+        }
+    }
+
+    fn scroll_up(&mut self) {
+        let line = self.grid.remove(self.top);
+        if self.top == 0 && !self.alternate {
+            self.scrollback.push_back(line);
+            self.trim_scrollback();
+        }
+        let blank = Line::new(self.cols, Cell::blank(self.pen.style()));
+        self.grid.insert(self.bottom, blank);
+    }
+
+    fn trim_scrollback(&mut self) {
+        while self.scrollback.len() > self.scrollback_limit {
+            self.scrollback.pop_front();
+        }
+    }
+
+    fn erase(&mut self, row: usize, cols: core::ops::Range<usize>) {
+        let blank = Cell::blank(self.pen.style());
+        if cols.end == self.cols {
+            self.grid[row].wrapped = false;
+        }
+        cell::erase(&mut self.grid[row].cells, cols, blank);
+    }
+
+    fn clear(&mut self) {
+        for row in 0..self.rows {
+            self.erase(row, 0..self.cols);
+        }
+    }
+
+    fn save_cursor(&mut self) {
+        self.saved = SavedCursor {
+            row: self.row,
+            col: self.col,
+            wrap_pending: self.wrap_pending,
+            pen: self.pen,
+            origin: self.origin,
+            charsets: self.charsets,
+        };
+    }
+
+    //Without a saved cursor this goes home with the default pen, as on a terminal.
+    fn restore_cursor(&mut self) {
+        let saved = self.saved;
+        self.move_to(saved.row, saved.col);
+        self.wrap_pending = saved.wrap_pending;
+        self.pen = saved.pen;
+        self.origin = saved.origin;
+        self.charsets = saved.charsets;
+    }
+
+    //The cursor and modes are shared between the two screens, only the grid and the saved
+    //cursor are swapped.
+    fn switch_screen(&mut self, alternate: bool) {
+        if self.alternate != alternate {
+            core::mem::swap(&mut self.grid, &mut self.other_grid);
+            core::mem::swap(&mut self.saved, &mut self.other_saved);
+            self.alternate = alternate;
+        }
+    }
+
+    fn move_to(&mut self, row: usize, col: usize) {
+        self.row = row.min(self.rows - 1);
+        self.col = col.min(self.cols - 1);
+        self.wrap_pending = false;
+    }
+
+    //Moves to an address from a sequence, which counts from the top margin in origin mode and
+    //can not leave the region then.
+    fn address(&mut self, row: usize, col: usize) {
+        if self.origin {
+            self.move_to((self.top + row).min(self.bottom), col);
+        } else {
+            self.move_to(row, col);
+        }
+    }
+
+    fn home(&mut self) {
+        self.address(0, 0);
+    }
+
+    //Counts of 0 move by one, as on a terminal.
+    fn count(n: u32) -> usize {
+        n.max(1) as usize
+    }
+
+    fn escape(&mut self, seq: &AnsiSequence) {
+        use AnsiSequence::*;
+
+        let (row, col) = (self.row, self.col);
+        match *seq {
+            CursorPos(row, col) => self.address(row.max(1) as usize - 1, col.max(1) as usize - 1),
+            //Vertical moves stop at the margins when they start inside the region.
+            CursorUp(n) => {
+                let limit = if row >= self.top { self.top } else { 0 };
+                self.move_to(row.saturating_sub(Self::count(n)).max(limit), col);
+            }
+            CursorDown(n) => {
+                let limit = if row <= self.bottom {
+                    self.bottom
+                } else {
+                    self.rows - 1
+                };
+                self.move_to((row + Self::count(n)).min(limit), col);
+            }
+            CursorForward(n) => self.move_to(row, col + Self::count(n)),
+            CursorBackward(n) => self.move_to(row, col.saturating_sub(Self::count(n))),
+            CursorSave | SaveCursor => self.save_cursor(),
+            CursorRestore | RestoreCursor => self.restore_cursor(),
+            SetAlternateScreen | SetAlternateScreenClear => self.switch_screen(true),
+            ResetAlternateScreen => self.switch_screen(false),
+            ResetAlternateScreenClear => {
+                if self.alternate {
+                    self.clear();
+                }
+                self.switch_screen(false);
+            }
+            //The cursor is saved on the main screen, and the alternate one starts out empty.
+            SetAlternateScreenSaveCursor => {
+                if !self.alternate {
+                    self.save_cursor();
+                }
+                self.switch_screen(true);
+                self.clear();
+            }
+            ResetAlternateScreenSaveCursor => {
+                self.switch_screen(false);
+                self.restore_cursor();
+            }
+            EraseDisplay => self.clear(),
+            EraseScrollback => self.scrollback.clear(),
+            EraseLine => {
+                self.erase(row, col..self.cols);
+                self.wrap_pending = false;
+            }
+            SetGraphicsMode(_) => self.pen.apply(seq),
+            SetUKG0
+            | SetUKG1
+            | SetUSG0
+            | SetUSG1
+            | SetG0SpecialChars
+            | SetG1SpecialChars
+            | SetG0AlternateChar
+            | SetG1AlternateChar
+            | SetG0AltAndSpecialGraph
+            | SetG1AltAndSpecialGraph
+            | SetG0DecSupplemental
+            | SetG1DecSupplemental
+            | SetG2Charset(_)
+            | SetG3Charset(_)
+            | LockShiftG2
+            | LockShiftG3
+            | SetSingleShift2
+            | SetSingleShift3 => self.charsets.apply(seq),
+            HideCursor => self.cursor_visible = false,
+            ShowCursor => self.cursor_visible = true,
+            SetNewLineMode => self.new_line_mode = true,
+            SetLineFeedMode => self.new_line_mode = false,
+            SetCol132 | SetCol80 => {
+                //The grid keeps its size, but switching columns clears it as on a terminal.
+                self.clear();
+                self.top = 0;
+                self.bottom = self.rows - 1;
+                self.home();
+            }
+            SetReverseVideo => self.reverse_video = true,
+            SetNormalVideo => self.reverse_video = false,
+            SetOriginRelative => {
+                self.origin = true;
+                self.home();
+            }
+            SetOriginAbsolute => {
+                self.origin = false;
+                self.home();
+            }
+            SetAutoWrap => self.autowrap = true,
+            ResetAutoWrap => {
+                self.autowrap = false;
+                self.wrap_pending = false;
+            }
+            SetTopAndBottom(top, bottom) => {
+                let top = top.max(1) as usize - 1;
+                let bottom = if bottom == 0 {
+                    self.rows - 1
+                } else {
+                    (bottom as usize - 1).min(self.rows - 1)
+                };
+                if top < bottom {
+                    self.top = top;
+                    self.bottom = bottom;
+                    self.home();
+                }
+            }
+            //Keyboard, mouse, paste and other input modes, and scrolling speed, do not change
+            //what is on the screen.
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,240 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+use super::{Cell, Screen};
+use crate::enums::AnsiSequence;
+use crate::style::Style;
+
+use core::fmt::{Result as DisplayResult, Write};
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+//Rewriting this many unchanged cells is no longer than moving the cursor over them.
+const MAX_GAP: usize = 3;
+
+//What a real terminal shows, as far as the screen knows.
+#[derive(Debug, Clone)]
+pub(super) struct Frame {
+    cells: Vec<Vec<Cell>>,
+    //`None` when the cursor is somewhere unknown, such as waiting to wrap.
+    cursor: Option<(usize, usize)>,
+    pen: Style,
+    cursor_visible: bool,
+    //The terminal has to be cleared before anything is drawn on it.
+    cleared: bool,
+}
+
+impl Frame {
+    //A terminal that has just been cleared, with the cursor at home.
+    pub(super) fn new(rows: usize, cols: usize) -> Self {
+        Frame {
+            cells: vec![vec![Cell::default(); cols]; rows],
+            cursor: Some((0, 0)),
+            pen: Style::default(),
+            cursor_visible: true,
+            cleared: true,
+        }
+    }
+
+    //A terminal that was resized, whatever is on it now is lost.
+    pub(super) fn unknown(rows: usize, cols: usize) -> Self {
+        Frame {
+            cursor: None,
+            cleared: false,
+            ..Frame::new(rows, cols)
+        }
+    }
+
+    fn move_to<W: Write>(&mut self, to: (usize, usize), writer: &mut W) -> DisplayResult {
+        if self.cursor == Some(to) {
+            return Ok(());
+        }
+        writer.write_str(&cursor_move(self.cursor, to))?;
+        self.cursor = Some(to);
+        Ok(())
+    }
+
+    fn set_pen<W: Write>(&mut self, style: Style, writer: &mut W) -> DisplayResult {
+        self.pen.write_diff(&style, writer)?;
+        self.pen = style;
+        Ok(())
+    }
+}
+
+//The shortest way of moving the cursor, from wherever it is when `from` is `None`.
+fn cursor_move(from: Option<(usize, usize)>, (row, col): (usize, usize)) -> String {
+    use AnsiSequence::*;
+
+    let absolute = CursorPos(row as u32 + 1, col as u32 + 1).to_string();
+    let relative = match from {
+        Some((r, _)) if r == row && col == 0 => String::from("\r"),
+        Some((r, c)) if r == row && col > c => CursorForward((col - c) as u32).to_string(),
+        Some((r, c)) if r == row => CursorBackward((c - col) as u32).to_string(),
+        Some((r, c)) if c == col && row > r => CursorDown((row - r) as u32).to_string(),
+        Some((r, c)) if c == col => CursorUp((r - row) as u32).to_string(),
+        _ => return absolute,
+    };
+
+    if relative.len() < absolute.len() {
+        relative
+    } else {
+        absolute
+    }
+}
+
+//Erasing fills with blanks in the background colour of the pen and no other attributes.
+fn erased_by(cell: &Cell) -> Option<Style> {
+    let style = Style {
+        bg: cell.style().bg,
+        ..Style::default()
+    };
+    if *cell == Cell::new(' ', style) {
+        Some(style)
+    } else {
+        None
+    }
+}
+
+impl Screen {
+    ///The cells that differ from what [`redraw`](Screen::redraw) last drew, as `(row, col)`.
+    ///After a [`resize`](Screen::resize) every cell is damaged.
+    pub fn damage(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let all = !self.front.cleared;
+        self.cells().enumerate().flat_map(move |(row, cells)| {
+            let front = &self.front.cells[row];
+            cells
+                .iter()
+                .zip(front)
+                .enumerate()
+                .filter(move |(_, (new, old))| all || new != old)
+                .map(move |(col, _)| (row, col))
+        })
+    }
+
+    ///Treats the screen as drawn, for when it was shown some other way than with
+    ///[`redraw`](Screen::redraw).
+    pub fn clear_damage(&mut self) {
+        self.front.cells = self.cells().map(<[Cell]>::to_vec).collect();
+        self.front.cursor = Some((self.row, self.col));
+        self.front.pen = self.pen.style();
+        self.front.cursor_visible = self.cursor_visible;
+        self.front.cleared = true;
+    }
+
+    ///Writes what brings a real terminal of the same size from the frame last drawn to what
+    ///is on the screen now, and treats that as drawn.
+    ///
+    ///Only damaged cells are written, moving the cursor the shortest way between them and
+    ///changing the pen with the shortest SGR sequence. Short gaps of unchanged cells are
+    ///written over instead of moved across, and the ends of rows are erased with
+    ///[`EraseLine`](AnsiSequence::EraseLine) where that is shorter. Before the first redraw the
+    ///terminal is taken to be cleared, with the cursor at home and the default pen. After a
+    ///resize it is cleared first.
+    pub fn redraw<W: Write>(&mut self, writer: &mut W) -> DisplayResult {
+        let mut front = core::mem::replace(&mut self.front, Frame::new(0, 0));
+
+        if !front.cleared {
+            front.set_pen(Style::default(), writer)?;
+            write!(writer, "{}", AnsiSequence::CursorPos(1, 1))?;
+            write!(writer, "{}", AnsiSequence::EraseDisplay)?;
+            front = Frame::new(self.rows, self.cols);
+        }
+
+        for (row, new) in self.grid.iter().enumerate() {
+            let new = &new.cells;
+            if *new == front.cells[row] {
+                continue;
+            }
+            self.redraw_row(row, new, &mut front, writer)?;
+            front.cells[row].clone_from(new);
+        }
+
+        if front.cursor_visible != self.cursor_visible {
+            let seq = if self.cursor_visible {
+                AnsiSequence::ShowCursor
+            } else {
+                AnsiSequence::HideCursor
+            };
+            write!(writer, "{}", seq)?;
+            front.cursor_visible = self.cursor_visible;
+        }
+        front.move_to((self.row, self.col), writer)?;
+
+        self.front = front;
+        Ok(())
+    }
+
+    fn redraw_row<W: Write>(
+        &self,
+        row: usize,
+        new: &[Cell],
+        front: &mut Frame,
+        writer: &mut W,
+    ) -> DisplayResult {
+        let old = &front.cells[row];
+        let cols = new.len();
+
+        //A run of blanks at the end of the row that one erase can draw.
+        let erase = new.last().and_then(erased_by).and_then(|style| {
+            let blank = new[cols - 1];
+            let start = new
+                .iter()
+                .rposition(|cell| *cell != blank)
+                .map_or(0, |i| i + 1);
+            if cols - start > MAX_GAP && new[start..] != old[start..] {
+                Some((start, style))
+            } else {
+                None
+            }
+        });
+        let end = erase.map_or(cols, |(start, _)| start);
+
+        let changed: Vec<bool> = (0..end).map(|col| new[col] != old[col]).collect();
+        let mut col = 0;
+        while col < end {
+            if !changed[col] {
+                col += 1;
+                continue;
+            }
+
+            //Runs take in short gaps, and whole wide characters.
+            let mut start = col;
+            if new[start].is_spacer() && start > 0 {
+                start -= 1;
+            }
+            let mut stop = col;
+            while stop < end {
+                match (stop..end.min(stop + MAX_GAP + 1)).find(|&i| changed[i]) {
+                    Some(next) => stop = next + 1,
+                    None => break,
+                }
+            }
+            if stop < cols && new[stop].is_spacer() {
+                stop += 1;
+            }
+
+            front.move_to((row, start), writer)?;
+            let mut text = String::new();
+            for cell in &new[start..stop] {
+                if cell.is_spacer() {
+                    continue;
+                }
+                front.set_pen(cell.style(), writer)?;
+                text.clear();
+                cell.push_to(&mut text);
+                writer.write_str(&text)?;
+            }
+            //The cursor waits to wrap at the last column, which no move can start from.
+            front.cursor = Some((row, stop)).filter(|_| stop < cols);
+            col = stop;
+        }
+
+        if let Some((start, style)) = erase {
+            front.move_to((row, start), writer)?;
+            front.set_pen(style, writer)?;
+            write!(writer, "{}", AnsiSequence::EraseLine)?;
+        }
+        Ok(())
+    }
+}
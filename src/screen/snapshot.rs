@@ -0,0 +1,529 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+use super::{Cell, Screen};
+use crate::style::{Color, Style};
+
+use core::fmt::{Display, Formatter, Result as DisplayResult, Write};
+use std::error::Error;
+use std::string::{String, ToString};
+use std::vec::Vec;
+use std::{fs, io, path::Path};
+use unicode_width::UnicodeWidthChar;
+
+const COLOR_NAMES: [&str; 16] = [
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "white",
+    "bright-black",
+    "bright-red",
+    "bright-green",
+    "bright-yellow",
+    "bright-blue",
+    "bright-magenta",
+    "bright-cyan",
+    "bright-white",
+];
+
+const LEGEND_SYMBOLS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+fn write_color(color: Color, formatter: &mut Formatter) -> DisplayResult {
+    match (color, color.ansi_index()) {
+        (_, Some(index)) => formatter.write_str(COLOR_NAMES[index as usize]),
+        (Color::Rgb(r, g, b), _) => write!(formatter, "#{:02x}{:02x}{:02x}", r, g, b),
+        (Color::Indexed(index), _) => write!(formatter, "{}", index),
+        _ => unreachable!("every other colour is named"),
+    }
+}
+
+fn parse_color(s: &str) -> Option<Color> {
+    if let Some(index) = COLOR_NAMES.iter().position(|name| *name == s) {
+        return Color::from_ansi(index as u8);
+    }
+    if let Some(hex) = s.strip_prefix('#') {
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return match hex.len() {
+            6 => Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?)),
+            _ => None,
+        };
+    }
+    s.parse().ok().map(Color::Indexed)
+}
+
+//The legend entry of a style, its attributes and colours separated by spaces.
+struct Describe(Style);
+
+impl Display for Describe {
+    fn fmt(&self, formatter: &mut Formatter) -> DisplayResult {
+        let style = &self.0;
+        let flags = [
+            (style.bold, "bold"),
+            (style.dim, "dim"),
+            (style.italic, "italic"),
+            (style.underline, "underline"),
+            (style.blink, "blink"),
+            (style.reverse, "reverse"),
+            (style.hidden, "hidden"),
+            (style.strikethrough, "strikethrough"),
+        ];
+
+        let mut first = true;
+        let mut space = |formatter: &mut Formatter| {
+            if !core::mem::replace(&mut first, false) {
+                formatter.write_char(' ')?;
+            }
+            Ok(())
+        };
+        for (on, name) in flags.iter() {
+            if *on {
+                space(formatter)?;
+                formatter.write_str(name)?;
+            }
+        }
+        if let Some(fg) = style.fg {
+            space(formatter)?;
+            formatter.write_str("fg=")?;
+            write_color(fg, formatter)?;
+        }
+        if let Some(bg) = style.bg {
+            space(formatter)?;
+            formatter.write_str("bg=")?;
+            write_color(bg, formatter)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_style(s: &str) -> Option<Style> {
+    let mut style = Style::default();
+    for word in s.split_whitespace() {
+        match word {
+            "bold" => style.bold = true,
+            "dim" => style.dim = true,
+            "italic" => style.italic = true,
+            "underline" => style.underline = true,
+            "blink" => style.blink = true,
+            "reverse" => style.reverse = true,
+            "hidden" => style.hidden = true,
+            "strikethrough" => style.strikethrough = true,
+            _ => {
+                if let Some(fg) = word.strip_prefix("fg=") {
+                    style.fg = Some(parse_color(fg)?);
+                } else if let Some(bg) = word.strip_prefix("bg=") {
+                    style.bg = Some(parse_color(bg)?);
+                } else {
+                    return None;
+                }
+            }
+        }
+    }
+    Some(style)
+}
+
+fn legend_symbol(index: usize) -> char {
+    LEGEND_SYMBOLS
+        .chars()
+        .nth(index)
+        .unwrap_or_else(|| core::char::from_u32(0x100 + index as u32).unwrap_or('?'))
+}
+
+///Where and why a snapshot could not be read.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SnapshotError {
+    pub line: usize,
+    pub message: &'static str,
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, formatter: &mut Formatter) -> DisplayResult {
+        write!(formatter, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for SnapshotError {}
+
+///The text form of what a [`Screen`] shows, for golden tests.
+///
+///```text
+///size 2x8
+///cursor 1,3
+///|$ ls    |
+///|a.txt   |
+///styles
+///|........|
+///|aaaaa...|
+///a bold fg=blue
+///```
+///
+///Every row is written between bars so that trailing blanks survive editors. A wide
+///character stands for both of its cells. When any cell is styled, a second grid gives each
+///cell a symbol, `.` for the default style, and a legend describes the other symbols with
+///their attributes and colours. Colours are named as in [`Color`], as a 256 colour index or
+///as `#rrggbb`. The cursor counts from 0 like [`Screen::cursor`], and is followed by `hidden`
+///when it is not visible.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Snapshot {
+    rows: usize,
+    cols: usize,
+    cursor: (usize, usize),
+    cursor_visible: bool,
+    cells: Vec<Vec<Cell>>,
+}
+
+impl Snapshot {
+    pub fn new(screen: &Screen) -> Self {
+        Snapshot {
+            rows: screen.rows(),
+            cols: screen.cols(),
+            cursor: screen.cursor(),
+            cursor_visible: screen.cursor_visible(),
+            cells: screen.cells().map(<[Cell]>::to_vec).collect(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, SnapshotError> {
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line));
+        let error = |line, message| SnapshotError { line, message };
+        let mut next = |message| lines.next().ok_or_else(|| error(0, message));
+
+        let (n, line) = next("missing size")?;
+        let (rows, cols) = line
+            .strip_prefix("size ")
+            .and_then(|size| {
+                let mut parts = size.split('x');
+                Some((parts.next()?.parse().ok()?, parts.next()?.parse().ok()?))
+            })
+            .ok_or_else(|| error(n, "expected `size <rows>x<cols>`"))?;
+
+        let (n, line) = next("missing cursor")?;
+        let (cursor, cursor_visible) = line
+            .strip_prefix("cursor ")
+            .and_then(|cursor| {
+                let (cursor, visible) = match cursor.strip_suffix(" hidden") {
+                    Some(cursor) => (cursor, false),
+                    None => (cursor, true),
+                };
+                let mut parts = cursor.split(',');
+                let row = parts.next()?.parse().ok()?;
+                let col = parts.next()?.parse().ok()?;
+                Some(((row, col), visible))
+            })
+            .ok_or_else(|| error(n, "expected `cursor <row>,<col>`"))?;
+
+        let mut cells = Vec::with_capacity(rows);
+        for _ in 0..rows {
+            let (n, line) = next("missing row")?;
+            let row = bars(line)
+                .and_then(|row| parse_row(row, cols))
+                .ok_or_else(|| error(n, "row does not fit the size"))?;
+            cells.push(row);
+        }
+
+        if let Some((n, line)) = lines.next() {
+            if line != "styles" {
+                return Err(error(n, "expected `styles`"));
+            }
+
+            let mut symbols = Vec::with_capacity(rows);
+            for _ in 0..rows {
+                let (n, line) = lines.next().ok_or_else(|| error(0, "missing style row"))?;
+                let row: Vec<char> = bars(line)
+                    .ok_or_else(|| error(n, "expected a style row"))?
+                    .chars()
+                    .collect();
+                if row.len() != cols {
+                    return Err(error(n, "style row does not fit the size"));
+                }
+                symbols.push((n, row));
+            }
+
+            let mut legend = Vec::new();
+            for (n, line) in lines {
+                let mut chars = line.chars();
+                let symbol = chars.next().ok_or_else(|| error(n, "empty legend entry"))?;
+                let style = parse_style(chars.as_str()).ok_or_else(|| error(n, "unknown style"))?;
+                legend.push((symbol, style));
+            }
+
+            for (row, (n, symbols)) in cells.iter_mut().zip(symbols) {
+                for (cell, symbol) in row.iter_mut().zip(symbols) {
+                    cell.style = match symbol {
+                        '.' => Style::default(),
+                        symbol => legend
+                            .iter()
+                            .find(|(s, _)| *s == symbol)
+                            .map(|(_, style)| *style)
+                            .ok_or_else(|| error(n, "symbol missing from the legend"))?,
+                    };
+                }
+            }
+        }
+
+        Ok(Snapshot {
+            rows,
+            cols,
+            cursor,
+            cursor_visible,
+            cells,
+        })
+    }
+
+    ///Reads a snapshot from a file, a golden file under `tests/` for instance.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Snapshot::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    ///Writes the snapshot to a file, to create or update a golden file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    ///`None` when `actual` looks like the snapshot, otherwise a report of every difference,
+    ///cell by cell, followed by the snapshot of `actual`.
+    pub fn diff(&self, actual: &Screen) -> Option<String> {
+        let actual = Snapshot::new(actual);
+        if *self == actual {
+            return None;
+        }
+
+        let mut report = String::new();
+        self.write_report(&actual, &mut report)
+            .expect("writing to a String can not fail");
+        Some(report)
+    }
+
+    fn write_report<W: Write>(&self, actual: &Snapshot, writer: &mut W) -> DisplayResult {
+        writeln!(writer, "screen does not match the snapshot")?;
+
+        if (self.rows, self.cols) != (actual.rows, actual.cols) {
+            writeln!(
+                writer,
+                "size: expected {}x{}, got {}x{}",
+                self.rows, self.cols, actual.rows, actual.cols
+            )?;
+            return write!(writer, "actual snapshot:\n{}", actual);
+        }
+
+        if (self.cursor, self.cursor_visible) != (actual.cursor, actual.cursor_visible) {
+            writeln!(
+                writer,
+                "cursor: expected {}, got {}",
+                CursorText(self),
+                CursorText(actual)
+            )?;
+        }
+
+        for (row, (expected, got)) in self.cells.iter().zip(&actual.cells).enumerate() {
+            if expected == got {
+                continue;
+            }
+            writeln!(writer, "row {}:", row)?;
+            writeln!(writer, "  expected |{}|", RowText(expected))?;
+            writeln!(writer, "  got      |{}|", RowText(got))?;
+
+            let marks: String = expected
+                .iter()
+                .zip(got)
+                .map(|(e, g)| if e == g { ' ' } else { '^' })
+                .collect();
+            writeln!(writer, "            {}", marks.trim_end())?;
+
+            for (col, (e, g)) in expected.iter().zip(got).enumerate() {
+                if e != g {
+                    writeln!(
+                        writer,
+                        "  col {}: expected {}, got {}",
+                        col,
+                        CellText(e),
+                        CellText(g)
+                    )?;
+                }
+            }
+        }
+
+        write!(writer, "actual snapshot:\n{}", actual)
+    }
+}
+
+fn bars(line: &str) -> Option<&str> {
+    line.strip_prefix('|')?.strip_suffix('|')
+}
+
+fn parse_row(text: &str, cols: usize) -> Option<Vec<Cell>> {
+    let mut row: Vec<Cell> = Vec::with_capacity(cols);
+    for c in text.chars() {
+        match c.width() {
+            Some(0) => row.last_mut()?.combine(c),
+            Some(2) => {
+                row.push(Cell::new(c, Style::default()));
+                row.push(Cell::spacer(Style::default()));
+            }
+            Some(_) => row.push(Cell::new(c, Style::default())),
+            None => return None,
+        }
+    }
+    if row.len() == cols {
+        Some(row)
+    } else {
+        None
+    }
+}
+
+struct RowText<'a>(&'a [Cell]);
+
+impl Display for RowText<'_> {
+    fn fmt(&self, formatter: &mut Formatter) -> DisplayResult {
+        let mut text = String::new();
+        for cell in self.0 {
+            cell.push_to(&mut text);
+        }
+        formatter.write_str(&text)
+    }
+}
+
+struct CellText<'a>(&'a Cell);
+
+impl Display for CellText<'_> {
+    fn fmt(&self, formatter: &mut Formatter) -> DisplayResult {
+        let cell = self.0;
+        if cell.is_spacer() {
+            formatter.write_str("wide character spacer")?;
+        } else {
+            let mut text = String::new();
+            cell.push_to(&mut text);
+            write!(formatter, "{:?}", text)?;
+        }
+        if !cell.style().is_default() {
+            write!(formatter, " [{}]", Describe(cell.style()))?;
+        }
+        Ok(())
+    }
+}
+
+struct CursorText<'a>(&'a Snapshot);
+
+impl Display for CursorText<'_> {
+    fn fmt(&self, formatter: &mut Formatter) -> DisplayResult {
+        let (row, col) = self.0.cursor;
+        write!(formatter, "{},{}", row, col)?;
+        if !self.0.cursor_visible {
+            formatter.write_str(" hidden")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Snapshot {
+    fn fmt(&self, formatter: &mut Formatter) -> DisplayResult {
+        writeln!(formatter, "size {}x{}", self.rows, self.cols)?;
+        writeln!(formatter, "cursor {}", CursorText(self))?;
+        for row in &self.cells {
+            writeln!(formatter, "|{}|", RowText(row))?;
+        }
+
+        let mut legend: Vec<Style> = Vec::new();
+        for cell in self.cells.iter().flatten() {
+            if !cell.style().is_default() && !legend.contains(&cell.style()) {
+                legend.push(cell.style());
+            }
+        }
+        if legend.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(formatter, "styles")?;
+        for row in &self.cells {
+            formatter.write_char('|')?;
+            for cell in row {
+                let symbol = match legend.iter().position(|style| *style == cell.style()) {
+                    Some(index) => legend_symbol(index),
+                    None => '.',
+                };
+                formatter.write_char(symbol)?;
+            }
+            writeln!(formatter, "|")?;
+        }
+        for (index, style) in legend.iter().enumerate() {
+            writeln!(formatter, "{} {}", legend_symbol(index), Describe(*style))?;
+        }
+        Ok(())
+    }
+}
+
+impl Screen {
+    ///The snapshot of what is on the screen now, see [`Snapshot`].
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot::new(self)
+    }
+}
+
+///Turns what [`assert_screen_eq!`](crate::assert_screen_eq) compares a screen with into a
+///snapshot.
+pub trait ExpectedSnapshot {
+    fn expected_snapshot(self) -> Result<Snapshot, SnapshotError>;
+}
+
+impl ExpectedSnapshot for &str {
+    fn expected_snapshot(self) -> Result<Snapshot, SnapshotError> {
+        Snapshot::parse(self)
+    }
+}
+
+impl ExpectedSnapshot for &String {
+    fn expected_snapshot(self) -> Result<Snapshot, SnapshotError> {
+        Snapshot::parse(self)
+    }
+}
+
+impl ExpectedSnapshot for Snapshot {
+    fn expected_snapshot(self) -> Result<Snapshot, SnapshotError> {
+        Ok(self)
+    }
+}
+
+impl ExpectedSnapshot for &Snapshot {
+    fn expected_snapshot(self) -> Result<Snapshot, SnapshotError> {
+        Ok(self.clone())
+    }
+}
+
+///Asserts that a [`Screen`](crate::Screen) looks like a snapshot, given as its text or as a
+///[`Snapshot`](crate::Snapshot). On a mismatch the panic message lists the differing cells
+///and the snapshot of the screen, ready to paste in.
+///
+///```
+///use ansi_parser::{assert_screen_eq, Screen};
+///
+///let mut screen = Screen::new(1, 4);
+///screen.process("ok");
+///assert_screen_eq!(screen, "size 1x4\ncursor 0,2\n|ok  |\n");
+///```
+#[macro_export]
+macro_rules! assert_screen_eq {
+    ($screen:expr, $expected:expr $(,)?) => {
+        $crate::assert_screen_eq!($screen, $expected, "screen assertion failed")
+    };
+    ($screen:expr, $expected:expr, $($arg:tt)+) => {{
+        let screen: &$crate::Screen = &$screen;
+        match $crate::ExpectedSnapshot::expected_snapshot($expected) {
+            Ok(expected) => {
+                if let Some(report) = expected.diff(screen) {
+                    panic!("{}\n{}", format_args!($($arg)+), report);
+                }
+            }
+            Err(error) => panic!(
+                "{}\ninvalid snapshot, {}\nactual snapshot:\n{}",
+                format_args!($($arg)+),
+                error,
+                screen.snapshot()
+            ),
+        }
+    }};
+}
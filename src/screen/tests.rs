@@ -0,0 +1,968 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+use super::*;
+use crate::assert_screen_eq;
+use crate::charset::Charset;
+use crate::style::Color;
+
+fn screen(rows: usize, cols: usize, input: &str) -> Screen {
+    let mut screen = Screen::new(rows, cols);
+    screen.process(input);
+    screen
+}
+
+//Every attribute at once, which takes more parameters than one sequence can carry.
+const LONG_STYLE: &str = "\x1b[1;2;3;4;5m\x1b[7;8;9m\x1b[38;2;1;2;3m\x1b[48;2;4;5;6mrich\x1b[m";
+
+#[test]
+fn test_empty() {
+    let screen = Screen::new(3, 4);
+    assert_eq!(screen.text(), "\n\n");
+    assert_eq!(screen.cursor(), (0, 0));
+    assert_eq!(screen.cells().count(), 3);
+    assert!(screen.cells().all(|row| row.len() == 4));
+}
+
+#[test]
+fn test_text_and_newlines() {
+    let screen = screen(3, 10, "hello\r\nworld");
+    assert_eq!(screen.text(), "hello\nworld\n");
+    assert_eq!(screen.cursor(), (1, 5));
+}
+
+#[test]
+fn test_line_feed_keeps_column() {
+    assert_eq!(screen(2, 10, "ab\ncd").text(), "ab\n  cd");
+    assert_eq!(screen(2, 10, "\x1b[20hab\ncd").text(), "ab\ncd");
+}
+
+#[test]
+fn test_scrolls_at_bottom() {
+    let screen = screen(2, 5, "one\r\ntwo\r\nthree");
+    assert_eq!(screen.text(), "two\nthree");
+    assert_eq!(screen.cursor(), (1, 4));
+}
+
+#[test]
+fn test_wraps_at_right_margin() {
+    let screen = screen(2, 4, "abcdef");
+    assert_eq!(screen.text(), "abcd\nef");
+    //The cursor stays on the last column until the next character is written.
+    let screen = self::screen(2, 4, "abcd");
+    assert_eq!(screen.cursor(), (0, 3));
+    assert_eq!(screen.text(), "abcd\n");
+}
+
+#[test]
+fn test_no_autowrap_overwrites_last_column() {
+    assert_eq!(screen(2, 4, "\x1b[?7labcdef").text(), "abcf\n");
+}
+
+#[test]
+fn test_cursor_position() {
+    let screen = screen(3, 5, "\x1b[2;3Hx\x1b[Hy\x1b[9;9Hz");
+    assert_eq!(screen.text(), "y\n  x\n    z");
+}
+
+#[test]
+fn test_cursor_moves_are_clamped() {
+    let mut screen = Screen::new(3, 5);
+    screen.process("\x1b[2;2H\x1b[9A");
+    assert_eq!(screen.cursor(), (0, 1));
+    screen.process("\x1b[9B");
+    assert_eq!(screen.cursor(), (2, 1));
+    screen.process("\x1b[9C");
+    assert_eq!(screen.cursor(), (2, 4));
+    screen.process("\x1b[9D");
+    assert_eq!(screen.cursor(), (2, 0));
+    screen.process("\x1b[C");
+    assert_eq!(screen.cursor(), (2, 1));
+}
+
+#[test]
+fn test_save_restore() {
+    let screen = screen(2, 10, "ab\x1b[s\r\ncd\x1b[uef");
+    assert_eq!(screen.text(), "abef\ncd");
+}
+
+#[test]
+fn test_erase() {
+    assert_eq!(screen(2, 5, "abc\r\ndef\x1b[2J").text(), "\n");
+    assert_eq!(screen(1, 5, "abcde\x1b[3D\x1b[K").text(), "a");
+}
+
+#[test]
+fn test_erase_uses_background() {
+    let screen = screen(1, 3, "\x1b[1;41m\x1b[K");
+    let blank = Cell::new(
+        ' ',
+        Style {
+            bg: Some(Color::Red),
+            ..Style::default()
+        },
+    );
+    assert_eq!(screen.row(0).unwrap(), &[blank; 3]);
+}
+
+#[test]
+fn test_styled_cells() {
+    let screen = screen(1, 5, "a\x1b[1;32mb\x1b[0mc");
+    let green = Style {
+        fg: Some(Color::Green),
+        bold: true,
+        ..Style::default()
+    };
+    assert_eq!(screen.cell(0, 0), Some(&Cell::new('a', Style::default())));
+    assert_eq!(screen.cell(0, 1), Some(&Cell::new('b', green)));
+    assert_eq!(screen.cell(0, 2), Some(&Cell::new('c', Style::default())));
+    assert_eq!(screen.cell(0, 5), None);
+    assert_eq!(screen.pen(), Style::default());
+}
+
+#[test]
+fn test_controls() {
+    assert_eq!(screen(1, 20, "a\tb\x07c\x08d").text(), "a       bd");
+    assert_eq!(screen(1, 10, "a\t\t\tb").text(), "a        b");
+}
+
+#[test]
+fn test_modes() {
+    let mut screen = Screen::new(5, 5);
+    screen.process("\x1b[?25l\x1b[?5h\x1b[?6h\x1b[2;4r");
+    assert!(!screen.cursor_visible());
+    assert!(screen.reverse_video());
+    assert!(screen.origin_mode());
+    assert_eq!(screen.scroll_region(), (1, 3));
+    screen.process("\x1b[?25h\x1b[?5l\x1b[?6l\x1b[?7l");
+    assert!(screen.cursor_visible());
+    assert!(!screen.reverse_video());
+    assert!(!screen.origin_mode());
+    assert!(!screen.autowrap());
+}
+
+#[test]
+fn test_column_mode_clears() {
+    let screen = screen(2, 5, "ab\x1b[?3h");
+    assert_eq!(screen.text(), "\n");
+    assert_eq!(screen.cursor(), (0, 0));
+}
+
+#[test]
+fn test_unknown_sequences_draw_nothing() {
+    assert_eq!(screen(1, 10, "a\x1b]0;title\x07b\x1b[2Sc").text(), "abc");
+}
+
+#[test]
+fn test_split_sequences() {
+    let mut screen = Screen::new(2, 10);
+    for piece in [
+        "\x1b[3",
+        "1mX",
+        "\x1b",
+        "[0mY\x1b]0;ti",
+        "tle\x1b",
+        "\\Z\x1b[2;",
+        "3H!",
+    ]
+    .iter()
+    {
+        screen.process(piece);
+    }
+    assert_eq!(screen.text(), "XYZ\n  !");
+    assert_eq!(
+        screen.cells().next().unwrap()[0].style().fg,
+        Some(Color::Red)
+    );
+    assert_eq!(screen.cells().next().unwrap()[1].style(), Style::default());
+
+    //An unterminated string is not held back for ever.
+    let mut screen = Screen::new(1, 10);
+    screen.process(&format!("\x1b]0;{}", "x".repeat(MAX_PARTIAL)));
+    screen.process("ok");
+    assert_eq!(screen.text(), "ok");
+}
+
+#[test]
+fn test_wide_characters() {
+    let screen = screen(1, 6, "a\u{6f22}\u{5b57}b");
+    assert_eq!(screen.text(), "a\u{6f22}\u{5b57}b");
+    assert_eq!(screen.cursor(), (0, 5));
+    assert_eq!(screen.cell(0, 1).unwrap().width(), 2);
+    assert!(screen.cell(0, 2).unwrap().is_spacer());
+    assert_eq!(screen.cell(0, 3).unwrap().c(), '\u{5b57}');
+    assert!(screen.cell(0, 4).unwrap().is_spacer());
+    assert_eq!(screen.cell(0, 5).unwrap().c(), 'b');
+}
+
+#[test]
+fn test_emoji() {
+    let screen = screen(1, 4, "\u{1f600}!");
+    assert_eq!(screen.text(), "\u{1f600}!");
+    assert_eq!(screen.cursor(), (0, 3));
+}
+
+#[test]
+fn test_wide_character_wraps_from_last_column() {
+    //xterm leaves the last column alone and puts the whole character on the next line.
+    let screen = screen(2, 5, "abcd\u{6f22}");
+    assert_eq!(screen.text(), "abcd\n\u{6f22}");
+    assert_eq!(screen.cursor(), (1, 2));
+    assert_eq!(screen.cell(0, 4), Some(&Cell::default()));
+}
+
+#[test]
+fn test_wide_character_without_autowrap() {
+    let screen = screen(2, 5, "\x1b[?7labcd\u{6f22}");
+    assert_eq!(screen.text(), "abc\u{6f22}\n");
+}
+
+#[test]
+fn test_wide_character_wider_than_screen() {
+    assert_eq!(screen(1, 1, "\u{6f22}a").text(), "a");
+}
+
+#[test]
+fn test_overwriting_half_of_wide_character() {
+    assert_eq!(
+        screen(1, 5, "\u{6f22}\u{5b57}\x1b[1;2Hx").text(),
+        " x\u{5b57}"
+    );
+    assert_eq!(
+        screen(1, 5, "\u{6f22}\u{5b57}\x1b[1;3Hx").text(),
+        "\u{6f22}x"
+    );
+    let screen = self::screen(1, 5, "\u{6f22}\u{5b57}\x1b[1;2Hxy");
+    assert_eq!(screen.text(), " xy");
+    assert!(!screen.cell(0, 3).unwrap().is_spacer());
+}
+
+#[test]
+fn test_erase_half_of_wide_character() {
+    assert_eq!(screen(1, 5, "a\u{6f22}\x1b[1;3H\x1b[K").text(), "a");
+}
+
+#[test]
+fn test_combining_marks() {
+    let screen = screen(1, 5, "e\u{301}a\u{300}\u{323}");
+    assert_eq!(screen.text(), "e\u{301}a\u{300}\u{323}");
+    assert_eq!(screen.cursor(), (0, 2));
+    assert_eq!(
+        screen.cell(0, 0).unwrap().combining().collect::<String>(),
+        "\u{301}"
+    );
+}
+
+#[test]
+fn test_combining_mark_on_wide_character() {
+    let screen = screen(1, 5, "\u{6f22}\u{3099}x");
+    assert_eq!(screen.text(), "\u{6f22}\u{3099}x");
+}
+
+#[test]
+fn test_combining_mark_at_last_column() {
+    assert_eq!(screen(2, 3, "abc\u{301}").text(), "abc\u{301}\n");
+}
+
+#[test]
+fn test_combining_marks_are_limited() {
+    assert_eq!(
+        screen(1, 3, "a\u{300}\u{301}\u{302}").text(),
+        "a\u{300}\u{301}"
+    );
+}
+
+#[test]
+fn test_combining_mark_at_start_is_dropped() {
+    assert_eq!(screen(1, 3, "\u{301}a").text(), "a");
+}
+
+//Numbers the rows 1 to `rows`, as vttest fills the screen before each test.
+fn numbered(rows: usize, cols: usize) -> Screen {
+    let mut screen = Screen::new(rows, cols);
+    for row in 1..=rows {
+        screen.process(&format!("\x1b[{};1H{}", row, row));
+    }
+    screen
+}
+
+#[test]
+fn test_scrolling_is_confined_to_region() {
+    let mut screen = numbered(5, 4);
+    screen.process("\x1b[2;4r\x1b[4;1H\n\n");
+    assert_eq!(screen.text(), "1\n4\n\n\n5");
+    assert_eq!(screen.cursor(), (3, 0));
+}
+
+#[test]
+fn test_line_feed_below_region() {
+    let mut screen = numbered(5, 4);
+    screen.process("\x1b[1;3r\x1b[5;1H\n\n");
+    assert_eq!(screen.text(), "1\n2\n3\n4\n5");
+    assert_eq!(screen.cursor(), (4, 0));
+}
+
+#[test]
+fn test_region_is_reset() {
+    let mut screen = numbered(3, 4);
+    screen.process("\x1b[1;2r\x1b[1;3r");
+    assert_eq!(screen.scroll_region(), (0, 2));
+    //A region of a single row is ignored.
+    screen.process("\x1b[2;2r");
+    assert_eq!(screen.scroll_region(), (0, 2));
+}
+
+#[test]
+fn test_origin_mode_addressing() {
+    let mut screen = Screen::new(5, 4);
+    screen.process("\x1b[2;4r\x1b[?6h");
+    assert_eq!(screen.cursor(), (1, 0));
+    screen.process("\x1b[1;1Ha\x1b[2;2Hb\x1b[9;3Hc");
+    assert_eq!(screen.text(), "\na\n b\n  c\n");
+    screen.process("\x1b[?6l");
+    assert_eq!(screen.cursor(), (0, 0));
+    screen.process("\x1b[5;4Hd");
+    assert_eq!(screen.text(), "\na\n b\n  c\n   d");
+}
+
+#[test]
+fn test_vertical_moves_stop_at_margins() {
+    let mut screen = Screen::new(5, 4);
+    screen.process("\x1b[2;4r\x1b[3;1H\x1b[9A");
+    assert_eq!(screen.cursor(), (1, 0));
+    screen.process("\x1b[9B");
+    assert_eq!(screen.cursor(), (3, 0));
+    //Outside the region the moves go as far as the screen edge.
+    screen.process("\x1b[5;1H\x1b[9A");
+    assert_eq!(screen.cursor(), (1, 0));
+    screen.process("\x1b[1;1H\x1b[9B");
+    assert_eq!(screen.cursor(), (3, 0));
+    screen.process("\x1b[5;1H\x1b[9B");
+    assert_eq!(screen.cursor(), (4, 0));
+}
+
+#[test]
+fn test_pending_wrap() {
+    let mut screen = Screen::new(2, 4);
+    screen.process("abcd");
+    assert_eq!(screen.cursor(), (0, 3));
+    assert!(screen.wrap_pending());
+    screen.process("\r");
+    assert!(!screen.wrap_pending());
+    screen.process("X");
+    assert_eq!(screen.text(), "Xbcd\n");
+}
+
+#[test]
+fn test_pending_wrap_is_cancelled_by_moves() {
+    assert_eq!(screen(2, 4, "abcd\x08X").text(), "abXd\n");
+    assert_eq!(screen(2, 4, "abcd\x1b[1;4HX").text(), "abcX\n");
+    assert_eq!(screen(2, 4, "abcd\x1b[KX").text(), "abcX\n");
+    assert_eq!(screen(2, 4, "abcd\x1b[1CX").text(), "abcX\n");
+    //A line feed cancels it as well, and keeps the column.
+    assert_eq!(screen(2, 4, "abcd\nX").text(), "abcd\n   X");
+}
+
+#[test]
+fn test_no_pending_wrap_without_autowrap() {
+    let mut screen = Screen::new(2, 4);
+    screen.process("\x1b[?7labcd");
+    assert!(!screen.wrap_pending());
+    screen.process("\x1b[?7hX");
+    assert_eq!(screen.text(), "abcX\n");
+}
+
+#[test]
+fn test_wrap_scrolls_region() {
+    let screen = screen(3, 4, "\x1b[3;1Hzz\x1b[1;2r\x1b[2;1Habcdefgh");
+    assert_eq!(screen.text(), "abcd\nefgh\nzz");
+    assert_eq!(screen.cursor(), (1, 3));
+}
+
+//After vttest's "Test of autowrap, mixing control and print characters": a letter at the
+//right margin of the bottom row and its lowercase at the start of the next one, reached by
+//wrapping or by control characters, so that both margins read in order.
+#[test]
+fn test_vttest_autowrap_mixed_with_controls() {
+    let mut screen = Screen::new(6, 5);
+    screen.process("\x1b[2;5r\x1b[?6h");
+    for (i, letter) in "ABCDEFGH".chars().enumerate() {
+        let lower = letter.to_ascii_lowercase();
+        match i % 4 {
+            0 => screen.process(&format!("\x1b[4;5H{}{}", letter, lower)),
+            1 => screen.process(&format!("\x1b[4;5H{}\n\r{}", letter, lower)),
+            2 => screen.process(&format!("\x1b[4;5H{}\r\n{}", letter, lower)),
+            _ => screen.process(&format!("\x1b[4;5H{}\n\x1b[4;1H{}", letter, lower)),
+        }
+    }
+    screen.process("\x1b[?6l");
+    assert_eq!(screen.text(), "\ne   F\nf   G\ng   H\nh\n");
+}
+
+//After vttest's "Test of cursor movements": a frame drawn with absolute and relative moves
+//from inside a scroll region in origin mode.
+#[test]
+fn test_vttest_frame_in_origin_mode() {
+    let mut screen = Screen::new(6, 6);
+    screen.process("\x1b[2;5r\x1b[?6h");
+    screen.process("\x1b[1;1H+\x1b[9C+\x1b[9B+\x1b[9D+");
+    screen.process("\x1b[2;1H|\x1b[9C|\x1b[9D\x1b[B|\x1b[9C|");
+    screen.process("\x1b[?6l");
+    assert_eq!(screen.text(), "\n+    +\n|    |\n|    |\n+    +\n");
+}
+
+#[test]
+fn test_alternate_screen_keeps_main_screen() {
+    let mut screen = Screen::new(3, 10);
+    screen.process("$ vim\r\n");
+    screen.process("\x1b[?1049h\x1b[Hfile contents\x1b[3;1H:wq");
+    assert!(screen.alternate_screen());
+    assert_eq!(screen.text(), "file conte\nnts\n:wq");
+    screen.process("\x1b[?1049l");
+    assert!(!screen.alternate_screen());
+    assert_eq!(screen.text(), "$ vim\n\n");
+    assert_eq!(screen.cursor(), (1, 0));
+}
+
+#[test]
+fn test_1049_clears_alternate_screen() {
+    let mut screen = Screen::new(2, 5);
+    screen.process("\x1b[?47hleft\x1b[?47l\x1b[?1049h");
+    assert_eq!(screen.text(), "\n");
+}
+
+#[test]
+fn test_47_keeps_contents_and_cursor() {
+    let mut screen = Screen::new(2, 5);
+    screen.process("ab\x1b[?47hcd");
+    assert_eq!(screen.text(), "  cd\n");
+    screen.process("\x1b[?47l");
+    assert_eq!(screen.text(), "ab\n");
+    assert_eq!(screen.cursor(), (0, 4));
+    screen.process("\x1b[?47h");
+    assert_eq!(screen.text(), "  cd\n");
+}
+
+#[test]
+fn test_1047_clears_on_leaving() {
+    let mut screen = Screen::new(2, 5);
+    screen.process("ab\x1b[?1047hcd\x1b[?1047l");
+    assert_eq!(screen.text(), "ab\n");
+    screen.process("\x1b[?1047h");
+    assert_eq!(screen.text(), "\n");
+}
+
+#[test]
+fn test_switching_to_current_screen_does_nothing() {
+    let mut screen = Screen::new(2, 5);
+    screen.process("ab\x1b[?47l");
+    assert_eq!(screen.text(), "ab\n");
+    screen.process("\x1b[?47hcd\x1b[?47h");
+    assert_eq!(screen.text(), "  cd\n");
+}
+
+#[test]
+fn test_decsc_saves_full_cursor() {
+    let mut screen = Screen::new(5, 5);
+    screen.process("\x1b[2;4r\x1b[?6h\x1b[2;3H\x1b[1;31m\x1b7");
+    screen.process("\x1b[0m\x1b[?6l\x1b[5;5H");
+    assert_eq!(screen.pen(), Style::default());
+    screen.process("\x1b8");
+    assert_eq!(screen.cursor(), (2, 2));
+    assert!(screen.origin_mode());
+    assert_eq!(
+        screen.pen(),
+        Style {
+            fg: Some(Color::Red),
+            bold: true,
+            ..Style::default()
+        }
+    );
+}
+
+#[test]
+fn test_cursor_save_is_the_same_as_decsc() {
+    let mut screen = Screen::new(2, 5);
+    screen.process("\x1b[1m\x1b[s\x1b[0m\x1b[2;2H\x1b[u");
+    assert!(screen.pen().bold);
+    assert_eq!(screen.cursor(), (0, 0));
+}
+
+#[test]
+fn test_restore_without_save() {
+    let mut screen = Screen::new(2, 5);
+    screen.process("\x1b[1m\x1b[2;2H\x1b8");
+    assert_eq!(screen.cursor(), (0, 0));
+    assert_eq!(screen.pen(), Style::default());
+}
+
+#[test]
+fn test_restore_keeps_pending_wrap() {
+    assert_eq!(screen(2, 3, "abc\x1b7\x1b[2;1H\x1b8d").text(), "abc\nd");
+}
+
+#[test]
+fn test_screens_have_their_own_saved_cursor() {
+    let mut screen = Screen::new(3, 5);
+    screen.process("\x1b[2;2H\x1b7\x1b[?47h\x1b[3;3H\x1b7\x1b[?47l\x1b8");
+    assert_eq!(screen.cursor(), (1, 1));
+    screen.process("\x1b[?47h\x1b8");
+    assert_eq!(screen.cursor(), (2, 2));
+}
+
+#[test]
+fn test_line_drawing() {
+    let screen = screen(2, 4, "\x1b(0lqqk\r\n\x1b(Bx  x");
+    assert_eq!(screen.text(), "┌──┐\nx  x");
+}
+
+#[test]
+fn test_shift_out_uses_g1() {
+    let screen = screen(1, 6, "\x1b)0a\u{e}mq\u{f}jb");
+    assert_eq!(screen.text(), "a└─jb");
+    assert_eq!(screen.charsets().shifted(), 0);
+}
+
+#[test]
+fn test_single_shift() {
+    let screen = screen(1, 6, "\x1b+0q\x1bOqq\x1bO\u{8}x");
+    assert_eq!(screen.text(), "q─│");
+}
+
+#[test]
+fn test_decsc_saves_charsets() {
+    let mut screen = Screen::new(1, 4);
+    screen.process("\x1b(0\x1b[1;3H\x1b7\x1b(B\x1b[1;1Hq\x1b8q");
+    assert_eq!(screen.text(), "q ─");
+    assert_eq!(screen.charsets().active(), Charset::DecSpecialGraphics);
+}
+
+#[test]
+fn test_scrollback_keeps_scrolled_lines() {
+    let screen = screen(2, 5, "one\r\ntwo\r\nthree\r\nfour");
+    assert_eq!(screen.scrollback().count(), 2);
+    assert_eq!(screen.history_text(), "one\ntwo\nthree\nfour");
+    assert_eq!(screen.text(), "three\nfour");
+}
+
+#[test]
+fn test_scrollback_limit() {
+    let mut screen = Screen::new(1, 5).with_scrollback(2);
+    screen.process("1\r\n2\r\n3\r\n4\r\n5");
+    assert_eq!(screen.history_text(), "3\n4\n5");
+
+    let mut screen = Screen::new(1, 5).with_scrollback(0);
+    screen.process("1\r\n2");
+    assert_eq!(screen.history_text(), "2");
+}
+
+#[test]
+fn test_erase_scrollback() {
+    let mut screen = Screen::new(1, 5);
+    screen.process("1\r\n2\r\n3\x1b[3J");
+    assert_eq!(screen.scrollback().count(), 0);
+    assert_eq!(screen.history_text(), "3");
+}
+
+#[test]
+fn test_no_scrollback_from_region_or_alternate_screen() {
+    let screen = screen(3, 5, "a\x1b[2;3r\x1b[3;1H\n\n");
+    assert_eq!(screen.scrollback().count(), 0);
+    let screen = self::screen(2, 5, "\x1b[?1049h1\r\n2\r\n3");
+    assert_eq!(screen.scrollback().count(), 0);
+}
+
+#[test]
+fn test_region_at_top_adds_to_scrollback() {
+    let screen = screen(3, 5, "a\r\nb\r\nc\x1b[1;2r\x1b[2;1H\n");
+    assert_eq!(screen.history_text(), "a\nb\n\nc");
+}
+
+#[test]
+fn test_history_is_styled() {
+    let screen = screen(2, 8, "\x1b[1mbold\x1b[0m\r\n\x1b[44m  \x1b[0m\r\nplain");
+    let lines: Vec<&[Cell]> = screen.history().collect();
+    assert!(lines[0][0].style().bold);
+    assert_eq!(
+        screen.history_styled_text(),
+        "\x1b[1mbold\x1b[m\n\x1b[44m  \x1b[m\nplain"
+    );
+}
+
+#[test]
+fn test_history_with_long_style_parses_back() {
+    let original = screen(1, 8, LONG_STYLE);
+    let copy = screen(1, 8, &original.history_styled_text());
+    assert_eq!(
+        copy.cells().collect::<Vec<_>>(),
+        original.cells().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_search() {
+    let screen = screen(2, 10, "error: one\r\nok\r\nan error\r\n\u{6f22}error");
+    let found: Vec<(usize, usize)> = screen.search("error").collect();
+    assert_eq!(found, vec![(0, 0), (2, 3), (3, 2)]);
+    assert_eq!(screen.search("missing").count(), 0);
+    assert_eq!(screen.search("").count(), 0);
+    assert_eq!(
+        screen.search("rr").collect::<Vec<_>>(),
+        vec![(0, 1), (2, 4), (3, 3)]
+    );
+}
+
+#[test]
+fn test_tracks_soft_wraps() {
+    let screen = screen(4, 4, "abcdef\r\nghij\r\nk");
+    assert!(screen.is_wrapped(0));
+    assert!(!screen.is_wrapped(1));
+    //Filling a row exactly is not a wrap until something goes on the next row.
+    assert!(!screen.is_wrapped(2));
+    assert!(!screen.is_wrapped(3));
+}
+
+#[test]
+fn test_erase_line_ends_soft_wrap() {
+    let screen = screen(2, 4, "abcdef\x1b[1;3H\x1b[K");
+    assert!(!screen.is_wrapped(0));
+}
+
+#[test]
+fn test_resize_narrower_rewraps() {
+    let mut screen = screen(4, 8, "$ echo hello world\r\nhi\r\n$ ");
+    assert_eq!(screen.text(), "ello wor\nld\nhi\n$");
+    screen.resize(6, 5);
+    assert_eq!(screen.text(), "$ ech\no hel\nlo wo\nrld\nhi\n$");
+    assert_eq!(screen.scrollback().count(), 0);
+    assert_eq!(screen.cursor(), (5, 2));
+}
+
+#[test]
+fn test_resize_wider_joins_soft_wraps() {
+    let mut screen = screen(4, 4, "abcdefgh\r\nij");
+    screen.resize(4, 10);
+    assert_eq!(screen.text(), "abcdefgh\nij\n\n");
+    assert_eq!(screen.cursor(), (1, 2));
+    assert!(!screen.is_wrapped(0));
+}
+
+#[test]
+fn test_resize_keeps_hard_newlines() {
+    let mut screen = screen(3, 4, "abcd\r\nef");
+    screen.resize(3, 10);
+    assert_eq!(screen.text(), "abcd\nef\n");
+}
+
+#[test]
+fn test_resize_anchors_cursor_to_character() {
+    let mut screen = screen(3, 10, "0123456789abcdef\x1b[2;3H");
+    assert_eq!(screen.cell(1, 2).unwrap().c(), 'c');
+    screen.resize(3, 6);
+    assert_eq!(screen.cursor(), (2, 0));
+    assert_eq!(screen.cell(2, 0).unwrap().c(), 'c');
+    screen.resize(3, 20);
+    assert_eq!(screen.cursor(), (0, 12));
+}
+
+#[test]
+fn test_resize_pushes_into_and_pulls_from_scrollback() {
+    let mut screen = screen(3, 10, "1\r\n2\r\n3\r\n4\r\n5");
+    assert_eq!(screen.text(), "3\n4\n5");
+    screen.resize(2, 10);
+    assert_eq!(screen.text(), "4\n5");
+    assert_eq!(screen.cursor(), (1, 1));
+    screen.resize(5, 10);
+    assert_eq!(screen.text(), "1\n2\n3\n4\n5");
+    assert_eq!(screen.cursor(), (4, 1));
+}
+
+#[test]
+fn test_resize_same_size_changes_nothing() {
+    let mut screen = screen(
+        3,
+        6,
+        "line\r\n\x1b[1mwrapped text\x1b[0m\x1b[H\x1b[2J\x1b[2;2H",
+    );
+    let before: Vec<Vec<Cell>> = screen.history().map(<[Cell]>::to_vec).collect();
+    screen.resize(3, 6);
+    let after: Vec<Vec<Cell>> = screen.history().map(<[Cell]>::to_vec).collect();
+    assert_eq!(before, after);
+    assert_eq!(screen.cursor(), (1, 1));
+}
+
+#[test]
+fn test_resize_wide_characters() {
+    let mut screen = screen(2, 5, "ab\u{6f22}\u{5b57}c");
+    assert_eq!(screen.text(), "ab\u{6f22}\n\u{5b57}c");
+    screen.resize(2, 4);
+    assert_eq!(screen.text(), "ab\u{6f22}\n\u{5b57}c");
+    screen.resize(2, 3);
+    assert_eq!(screen.history_text(), "ab\n\u{6f22}\n\u{5b57}c");
+}
+
+#[test]
+fn test_resize_keeps_styles() {
+    let mut screen = screen(2, 4, "\x1b[1mabcdef");
+    screen.resize(2, 8);
+    assert_eq!(screen.history_styled_text(), "\x1b[1mabcdef\x1b[m\n");
+}
+
+#[test]
+fn test_resize_alternate_screen_is_cut() {
+    let mut screen = screen(2, 6, "main text\x1b[?1049h\x1b[Hab\r\nabcdef");
+    screen.resize(2, 3);
+    assert_eq!(screen.text(), "ab\nabc");
+    assert_eq!(screen.cursor(), (1, 2));
+    screen.process("\x1b[?1049l");
+    assert_eq!(screen.text(), "n t\next");
+}
+
+#[test]
+fn test_resize_resets_scroll_region() {
+    let mut screen = screen(4, 4, "\x1b[2;3r");
+    screen.resize(6, 4);
+    assert_eq!(screen.scroll_region(), (0, 5));
+    assert_eq!(screen.rows(), 6);
+    assert_eq!(screen.cols(), 4);
+}
+
+fn redraw(screen: &mut Screen) -> String {
+    let mut out = String::new();
+    screen.redraw(&mut out).unwrap();
+    out
+}
+
+//Plays the redraws on a second screen, which has to end up the same as the first.
+fn assert_redraws(steps: &[&str], rows: usize, cols: usize) {
+    let mut screen = Screen::new(rows, cols);
+    let mut terminal = Screen::new(rows, cols);
+    for step in steps {
+        screen.process(step);
+        terminal.process(&redraw(&mut screen));
+        let expected: Vec<&[Cell]> = screen.cells().collect();
+        let drawn: Vec<&[Cell]> = terminal.cells().collect();
+        assert_eq!(drawn, expected, "after {:?}", step);
+        assert_eq!(terminal.cursor(), screen.cursor(), "after {:?}", step);
+        assert_eq!(screen.damage().count(), 0);
+    }
+}
+
+#[test]
+fn test_damage() {
+    let mut screen = Screen::new(2, 5);
+    assert_eq!(screen.damage().count(), 0);
+    screen.process("ab\x1b[2;5Hc");
+    assert_eq!(
+        screen.damage().collect::<Vec<_>>(),
+        vec![(0, 0), (0, 1), (1, 4)]
+    );
+    screen.clear_damage();
+    assert_eq!(screen.damage().count(), 0);
+    //Writing the same cell again is no change.
+    screen.process("\x1b[1;1Ha");
+    assert_eq!(screen.damage().count(), 0);
+}
+
+#[test]
+fn test_redraw_writes_only_changes() {
+    let mut screen = Screen::new(2, 10);
+    screen.process("hello");
+    assert_eq!(redraw(&mut screen), "hello");
+    assert_eq!(redraw(&mut screen), "");
+    screen.process("\x1b[1;2HE");
+    assert_eq!(redraw(&mut screen), "\x1b[4DE");
+}
+
+#[test]
+fn test_redraw_cursor_moves() {
+    let mut screen = Screen::new(5, 20);
+    screen.process("\x1b[5;15H");
+    assert_eq!(redraw(&mut screen), "\x1b[5;15H");
+    screen.process("\x1b[2;15H");
+    assert_eq!(redraw(&mut screen), "\x1b[3A");
+    screen.process("\r");
+    assert_eq!(redraw(&mut screen), "\r");
+}
+
+#[test]
+fn test_redraw_bridges_short_gaps() {
+    let mut screen = Screen::new(1, 20);
+    screen.process("abcdefghij");
+    redraw(&mut screen);
+    screen.process("\x1b[1;1HX\x1b[1;4HY\x1b[1;10HZ");
+    assert_eq!(redraw(&mut screen), "\rXbcY\x1b[5CZ");
+}
+
+#[test]
+fn test_redraw_styles() {
+    let mut screen = Screen::new(1, 10);
+    screen.process("a\x1b[1;31mb\x1b[0mc");
+    assert_eq!(redraw(&mut screen), "a\x1b[1;31mb\x1b[mc");
+}
+
+#[test]
+fn test_redraw_long_style() {
+    assert_redraws(&[LONG_STYLE], 1, 8);
+}
+
+#[test]
+fn test_redraw_erases_end_of_line() {
+    let mut screen = Screen::new(2, 20);
+    screen.process("a long line of text");
+    redraw(&mut screen);
+    screen.process("\r\x1b[Kshort");
+    assert_eq!(redraw(&mut screen), "\rshort\x1b[K");
+}
+
+#[test]
+fn test_redraw_erase_with_background() {
+    let mut screen = Screen::new(1, 10);
+    screen.process("\x1b[44m\x1b[K\x1b[0m");
+    assert_eq!(redraw(&mut screen), "\x1b[44m\x1b[K");
+}
+
+#[test]
+fn test_redraw_cursor_visibility() {
+    let mut screen = Screen::new(1, 10);
+    screen.process("\x1b[?25l");
+    assert_eq!(redraw(&mut screen), "\x1b[?25l");
+    screen.process("\x1b[?25h");
+    assert_eq!(redraw(&mut screen), "\x1b[?25h");
+}
+
+#[test]
+fn test_redraw_after_resize_clears() {
+    let mut screen = Screen::new(2, 10);
+    screen.process("abc");
+    redraw(&mut screen);
+    screen.resize(2, 5);
+    assert_eq!(screen.damage().count(), 10);
+    assert_eq!(redraw(&mut screen), "\x1b[H\x1b[2Jabc");
+}
+
+#[test]
+fn test_redraw_matches_screen() {
+    assert_redraws(
+        &[
+            "$ ls\r\n",
+            "\x1b[1;34mdir\x1b[0m  file.txt  \x1b[32mscript.sh\x1b[0m\r\n$ ",
+            "\x1b[?1049h\x1b[H\x1b[2J",
+            "\x1b[7m top bar \x1b[0m\x1b[3;1H\u{6f22}\u{5b57} e\u{301}",
+            "\x1b[2;3H\x1b[41m\x1b[K\x1b[0m\x1b[1;1Hx",
+            "\x1b[3;2Hy\x1b[5;16Hzz",
+            "\x1b[?1049l",
+            "more text that wraps around the right margin\r\n",
+        ],
+        5,
+        16,
+    );
+}
+
+#[test]
+fn test_snapshot_format() {
+    let screen = screen(
+        3,
+        8,
+        "$ ls\r\n\x1b[1;34mdir\x1b[0m \x1b[38;5;208mx\x1b[48;2;1;2;255m\u{6f22}\x1b[0m\r\ne\u{301}",
+    );
+    let expected = "\
+size 3x8
+cursor 2,1
+|$ ls    |
+|dir x\u{6f22} |
+|e\u{301}       |
+styles
+|........|
+|aaa.bcc.|
+|........|
+a bold fg=blue
+b fg=208
+c fg=208 bg=#0102ff
+";
+    assert_eq!(screen.snapshot().to_string(), expected);
+    assert_eq!(Snapshot::parse(expected), Ok(screen.snapshot()));
+}
+
+#[test]
+fn test_snapshot_without_styles() {
+    let screen = screen(2, 3, "ab\x1b[?25l");
+    assert_eq!(
+        screen.snapshot().to_string(),
+        "size 2x3\ncursor 0,2 hidden\n|ab |\n|   |\n"
+    );
+}
+
+#[test]
+fn test_snapshot_parse_errors() {
+    let error = |text: &str| Snapshot::parse(text).unwrap_err();
+    assert_eq!(error("").message, "missing size");
+    assert_eq!(error("size 2by3").line, 1);
+    assert_eq!(error("size 1x3\ncursor 0,0\n|abcd|").line, 3);
+    assert_eq!(
+        error("size 1x3\ncursor 0,0\n|abc|\nstyles\n|a..|").message,
+        "symbol missing from the legend"
+    );
+    assert_eq!(
+        error("size 1x3\ncursor 0,0\n|abc|\nstyles\n|a..|\na sparkly").message,
+        "unknown style"
+    );
+}
+
+#[test]
+fn test_snapshot_load_and_save() {
+    let path = std::env::temp_dir().join(format!("ansi-parser-snapshot-{}", std::process::id()));
+    let screen = screen(2, 4, "\x1b[7mhi\x1b[0m");
+    screen.snapshot().save(&path).unwrap();
+    let loaded = Snapshot::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, screen.snapshot());
+    assert_screen_eq!(screen, loaded);
+}
+
+#[test]
+fn test_assert_screen_eq() {
+    let screen = screen(2, 4, "ok");
+    assert_screen_eq!(screen, "size 2x4\ncursor 0,2\n|ok  |\n|    |\n");
+    assert_screen_eq!(
+        screen,
+        &screen.snapshot().to_string(),
+        "with a {}",
+        "message"
+    );
+}
+
+#[test]
+fn test_snapshot_diff_report() {
+    let screen = screen(2, 4, "ab\r\n\x1b[1mcd");
+    let expected = Snapshot::parse("size 2x4\ncursor 0,0\n|ab  |\n|cx  |\n").unwrap();
+    let report = expected.diff(&screen).unwrap();
+    assert!(
+        report.contains("cursor: expected 0,0, got 1,2\n"),
+        "{}",
+        report
+    );
+    assert!(
+        report.contains(
+            "row 1:\n  expected |cx  |\n  got      |cd  |\n            ^^\n\
+             \x20 col 0: expected \"c\", got \"c\" [bold]\n\
+             \x20 col 1: expected \"x\", got \"d\" [bold]\n"
+        ),
+        "{}",
+        report
+    );
+    assert!(report.ends_with(&format!("actual snapshot:\n{}", screen.snapshot())));
+}
+
+#[test]
+#[should_panic(expected = "size: expected 1x4, got 2x4")]
+fn test_assert_screen_eq_size_mismatch() {
+    assert_screen_eq!(Screen::new(2, 4), "size 1x4\ncursor 0,0\n|    |\n");
+}
+
+#[test]
+#[should_panic(expected = "invalid snapshot, line 1")]
+fn test_assert_screen_eq_invalid_snapshot() {
+    assert_screen_eq!(Screen::new(2, 4), "nonsense");
+}
@@ -0,0 +1,262 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+#[cfg(test)]
+mod tests;
+
+use std::borrow::Cow;
+
+///What to do with one category of escape sequence or control character.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum StripAction {
+    Keep,
+    Drop,
+    ///Put the character in its place, `'\u{fffd}'` for instance.
+    Replace(char),
+}
+
+///Decides per category what [`strip`] does with what it finds. Newlines and tabs are always
+///kept as text.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct StripPolicy {
+    ///`SetGraphicsMode`, `ESC[...m`.
+    pub sgr: StripAction,
+    ///Cursor movement, `ESC[...H` and friends, cursor save and restore, index and reverse
+    ///index.
+    pub cursor: StripAction,
+    ///Operating system commands, `ESC]...BEL`, apart from hyperlinks.
+    pub osc: StripAction,
+    ///OSC 8 hyperlinks, `ESC]8;;url ESC\`.
+    pub hyperlinks: StripAction,
+    ///Device control strings, `ESC P...ESC\`, along with the SOS, PM and APC strings.
+    pub dcs: StripAction,
+    ///C0 control characters and DEL, including a carriage return and a lone `ESC`.
+    pub c0: StripAction,
+    ///Every other escape sequence, whether the parser knows it or not.
+    pub other: StripAction,
+}
+
+impl StripPolicy {
+    ///Removes every escape sequence and control character.
+    pub const DROP_ALL: StripPolicy = StripPolicy {
+        sgr: StripAction::Drop,
+        cursor: StripAction::Drop,
+        osc: StripAction::Drop,
+        hyperlinks: StripAction::Drop,
+        dcs: StripAction::Drop,
+        c0: StripAction::Drop,
+        other: StripAction::Drop,
+    };
+
+    ///Keeps colours and text attributes, for logs that are viewed in a terminal later.
+    pub const KEEP_SGR: StripPolicy = StripPolicy {
+        sgr: StripAction::Keep,
+        ..StripPolicy::DROP_ALL
+    };
+
+    ///Keeps colours and hyperlinks.
+    pub const KEEP_HYPERLINKS: StripPolicy = StripPolicy {
+        sgr: StripAction::Keep,
+        hyperlinks: StripAction::Keep,
+        ..StripPolicy::DROP_ALL
+    };
+
+    fn action(&self, category: Category) -> StripAction {
+        match category {
+            Category::Text => StripAction::Keep,
+            Category::Sgr => self.sgr,
+            Category::Cursor => self.cursor,
+            Category::Osc => self.osc,
+            Category::Hyperlink => self.hyperlinks,
+            Category::Dcs => self.dcs,
+            Category::C0 => self.c0,
+            Category::Other => self.other,
+        }
+    }
+}
+
+impl Default for StripPolicy {
+    fn default() -> Self {
+        StripPolicy::DROP_ALL
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum Category {
+    Text,
+    Sgr,
+    Cursor,
+    Osc,
+    Hyperlink,
+    Dcs,
+    C0,
+    Other,
+}
+
+fn is_control(c: char) -> bool {
+    c.is_control() && c != '\n' && c != '\t'
+}
+
+//Returns the length of a string sequence body up to and including its terminator, BEL being
+//allowed for OSC only, or `None` when the input ends before the terminator.
+fn string_len(body: &str, bel: bool) -> Option<usize> {
+    let mut chars = body.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\u{7}' if bel => return Some(i + 1),
+            '\u{9c}' => return Some(i + c.len_utf8()),
+            '\u{1b}' => {
+                return match chars.peek() {
+                    Some((_, '\\')) => Some(i + 2),
+                    Some(_) => Some(i),
+                    None => None,
+                };
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+//Returns the length of what follows the introducer of a sequence, which is `ESC` and `c` or
+//the C1 control standing for them, or `None` when the input ends before the sequence does.
+fn body_len(c: char, body: &str) -> (Option<usize>, Category) {
+    match c {
+        '[' => {
+            let params = body
+                .find(|c| !('0'..='?').contains(&c))
+                .unwrap_or(body.len());
+            let intermediates = body[params..]
+                .find(|c| !(' '..='/').contains(&c))
+                .map(|i| params + i)
+                .unwrap_or(body.len());
+            match body[intermediates..].chars().next() {
+                Some(c) if ('@'..='~').contains(&c) => {
+                    //A private marker or an intermediate makes it some other sequence with
+                    //the same final character, such as XTMODKEYS for `m`.
+                    let plain = intermediates == params && !body.starts_with(['<', '=', '>', '?']);
+                    let category = match c {
+                        'm' if plain => Category::Sgr,
+                        'A'..='H' | 'a' | 'd' | 'e' | 'f' | '`' | 's' | 'u' if plain => {
+                            Category::Cursor
+                        }
+                        _ => Category::Other,
+                    };
+                    (Some(intermediates + 1), category)
+                }
+                Some(_) => (Some(intermediates), Category::Other),
+                None => (None, Category::Other),
+            }
+        }
+        ']' => {
+            let category = if body.starts_with("8;") {
+                Category::Hyperlink
+            } else {
+                Category::Osc
+            };
+            (string_len(body, true), category)
+        }
+        'P' | 'X' | '^' | '_' => (string_len(body, false), Category::Dcs),
+        '7' | '8' | 'D' | 'E' | 'M' => (Some(0), Category::Cursor),
+        _ => (Some(0), Category::Other),
+    }
+}
+
+//Splits the sequence starting with the `ESC` at the start of `input` off by its syntax alone,
+//so that sequences the parser does not know are still recognised. The length is `None` when
+//the input ends before the sequence does.
+fn escape_parts(input: &str) -> (Option<usize>, Category) {
+    let rest = &input[1..];
+
+    match rest.chars().next() {
+        None => (None, Category::C0),
+        Some(c) if (' '..='/').contains(&c) => {
+            let intermediates = rest
+                .find(|c| !(' '..='/').contains(&c))
+                .unwrap_or(rest.len());
+            match rest[intermediates..].chars().next() {
+                Some(c) if ('0'..='~').contains(&c) => {
+                    (Some(1 + intermediates + 1), Category::Other)
+                }
+                Some(_) => (Some(1 + intermediates), Category::Other),
+                None => (None, Category::Other),
+            }
+        }
+        Some(c) if ('0'..='~').contains(&c) => {
+            let (len, category) = body_len(c, &rest[1..]);
+            (len.map(|len| 2 + len), category)
+        }
+        Some(_) => (Some(1), Category::C0),
+    }
+}
+
+//Like `escape_parts`, with a sequence that is not finished running to the end of the input.
+pub(crate) fn escape_len(input: &str) -> (usize, Category) {
+    let (len, category) = escape_parts(input);
+    (len.unwrap_or(input.len()), category)
+}
+
+//Where the escape sequence starts that `input` ends in the middle of, if there is one.
+pub(crate) fn unfinished_escape(input: &str) -> Option<usize> {
+    let mut pos = 0;
+    while let Some(i) = input[pos..].find('\u{1b}') {
+        let start = pos + i;
+        match escape_parts(&input[start..]).0 {
+            Some(len) => pos = start + len,
+            None => return Some(start),
+        }
+    }
+    None
+}
+
+fn next_segment(input: &str) -> (usize, Category) {
+    let mut chars = input.chars();
+    match chars.next() {
+        Some('\u{1b}') => escape_len(input),
+        //A C1 control stands for `ESC` followed by the control less 0x40, `[` for CSI and so on.
+        Some(c @ '\u{80}'..='\u{9f}') => {
+            let body = chars.as_str();
+            let (len, category) = body_len(char::from(c as u8 - 0x40), body);
+            (c.len_utf8() + len.unwrap_or(body.len()), category)
+        }
+        Some(c) if is_control(c) => (c.len_utf8(), Category::C0),
+        _ => (
+            input.find(is_control).unwrap_or(input.len()),
+            Category::Text,
+        ),
+    }
+}
+
+///Removes escape sequences and control characters from `input` as `policy` says, borrowing
+///`input` when nothing had to be changed.
+///
+///Sequences are recognised by their syntax, so unknown and malformed sequences are stripped
+///too instead of being left behind as text. An 8-bit C1 control, such as `'\u{9b}'` for CSI,
+///is taken the same as the `ESC` sequence it stands for.
+pub fn strip<'a>(input: &'a str, policy: &StripPolicy) -> Cow<'a, str> {
+    let mut out: Option<String> = None;
+    let mut pos = 0;
+
+    while pos < input.len() {
+        let (len, category) = next_segment(&input[pos..]);
+        let segment = &input[pos..pos + len];
+
+        match (policy.action(category), &mut out) {
+            (StripAction::Keep, Some(out)) => out.push_str(segment),
+            (StripAction::Keep, None) => {}
+            (action, out) => {
+                let out = out.get_or_insert_with(|| String::from(&input[..pos]));
+                if let StripAction::Replace(c) = action {
+                    out.push(c);
+                }
+            }
+        }
+        pos += len;
+    }
+
+    match out {
+        Some(out) => Cow::Owned(out),
+        None => Cow::Borrowed(input),
+    }
+}
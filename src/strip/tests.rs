@@ -0,0 +1,138 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+use super::*;
+
+#[test]
+fn test_borrows_plain_text() {
+    let input = "hello\tworld\n";
+    assert!(matches!(strip(input, &StripPolicy::DROP_ALL), Cow::Borrowed(s) if s == input));
+}
+
+#[test]
+fn test_borrows_when_everything_is_kept() {
+    let input = "\x1b[1;31mred\x1b[0m";
+    assert!(matches!(
+        strip(input, &StripPolicy::KEEP_SGR),
+        Cow::Borrowed(_)
+    ));
+}
+
+#[test]
+fn test_drop_all() {
+    let input = "\x1b[2J\x1b[1;1H\x1b[32mok\x1b[m \x1b]0;title\x07done\r\n";
+    assert_eq!(strip(input, &StripPolicy::DROP_ALL), "ok done\n");
+}
+
+#[test]
+fn test_keep_sgr() {
+    let input = "\x1b[3A\x1b[1mbold\x1b[0m\x1b[K";
+    assert_eq!(strip(input, &StripPolicy::KEEP_SGR), "\x1b[1mbold\x1b[0m");
+}
+
+#[test]
+fn test_keep_hyperlinks() {
+    let input = "\x1b]2;title\x1b\\\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x1b\\";
+    assert_eq!(
+        strip(input, &StripPolicy::KEEP_HYPERLINKS),
+        "\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x1b\\"
+    );
+    assert_eq!(strip(input, &StripPolicy::DROP_ALL), "link");
+}
+
+#[test]
+fn test_unknown_sequences_do_not_leak() {
+    //Scroll up, DECSCUSR, a private mode the parser does not know and a charset designation.
+    let input = "a\x1b[2S\x1b[2 q\x1b[?1049hb\x1b(0c\x1b=d";
+    assert_eq!(strip(input, &StripPolicy::DROP_ALL), "abcd");
+}
+
+#[test]
+fn test_dcs_and_other_strings() {
+    let input = "a\x1bPq#0;2;0;0;0#0~~\x1b\\b\x1b_apc\x1b\\c\x1b^pm\x1b\\d";
+    assert_eq!(strip(input, &StripPolicy::DROP_ALL), "abcd");
+}
+
+#[test]
+fn test_unterminated_sequences() {
+    assert_eq!(strip("a\x1b]0;title", &StripPolicy::DROP_ALL), "a");
+    assert_eq!(strip("a\x1b[12;", &StripPolicy::DROP_ALL), "a");
+    assert_eq!(strip("a\x1b", &StripPolicy::DROP_ALL), "a");
+}
+
+#[test]
+fn test_malformed_csi_ends_at_invalid_byte() {
+    assert_eq!(strip("a\x1b[1;\u{e9}b", &StripPolicy::DROP_ALL), "a\u{e9}b");
+}
+
+#[test]
+fn test_replace() {
+    let policy = StripPolicy {
+        c0: StripAction::Replace('\u{fffd}'),
+        ..StripPolicy::DROP_ALL
+    };
+    assert_eq!(
+        strip("a\x07b\x08\x1b[1mc\x7f", &policy),
+        "a\u{fffd}b\u{fffd}c\u{fffd}"
+    );
+
+    let policy = StripPolicy {
+        cursor: StripAction::Replace(' '),
+        ..StripPolicy::KEEP_SGR
+    };
+    assert_eq!(strip("a\x1b[5Cb\x1b7\x1b[?25l", &policy), "a b ");
+}
+
+#[test]
+fn test_private_csi_is_not_cursor_movement() {
+    let policy = StripPolicy {
+        cursor: StripAction::Keep,
+        ..StripPolicy::DROP_ALL
+    };
+    assert_eq!(
+        strip("\x1b[2A\x1b[>1u\x1b[?1049h\x1b[s", &policy),
+        "\x1b[2A\x1b[s"
+    );
+    assert_eq!(
+        strip(
+            "\x1b[1m\x1b[>4;2m\x1b[?4mhi\x1b[>4m",
+            &StripPolicy::KEEP_SGR
+        ),
+        "\x1b[1mhi"
+    );
+}
+
+#[test]
+fn test_osc_terminated_by_c1_st() {
+    assert_eq!(strip("\x1b]0;t\u{9c}x", &StripPolicy::DROP_ALL), "x");
+}
+
+#[test]
+fn test_c1_controls_are_taken_as_escapes() {
+    //CSI, OSC, DCS, APC, ST, IND and a C1 control without a 7-bit string of its own, SS2.
+    let input = "\u{9b}1;31mred\u{9b}m \u{9d}0;t\x07a\u{90}q\u{9c}b\u{9f}x\x1b\\c\u{84}\u{8e}d";
+    assert_eq!(strip(input, &StripPolicy::DROP_ALL), "red abcd");
+    assert_eq!(
+        strip(input, &StripPolicy::KEEP_SGR),
+        "\u{9b}1;31mred\u{9b}m abcd"
+    );
+    let policy = StripPolicy {
+        cursor: StripAction::Keep,
+        ..StripPolicy::DROP_ALL
+    };
+    assert_eq!(strip("a\u{9b}2Ab\u{84}", &policy), "a\u{9b}2Ab\u{84}");
+    assert_eq!(strip("a\u{9d}0;title", &StripPolicy::DROP_ALL), "a");
+}
+
+#[test]
+fn test_unfinished_escape() {
+    assert_eq!(unfinished_escape("ab"), None);
+    assert_eq!(unfinished_escape("a\x1b[31mb"), None);
+    assert_eq!(unfinished_escape("a\x1b[31mb\x1b"), Some(7));
+    assert_eq!(unfinished_escape("a\x1b[3"), Some(1));
+    assert_eq!(unfinished_escape("a\x1b(\x1b]0;t\x1b"), Some(3));
+    assert_eq!(unfinished_escape("\x1b]0;t\x1b\\\x1b[?25"), Some(7));
+    //A malformed sequence ends where it stops making sense, it is not waiting for more.
+    assert_eq!(unfinished_escape("\x1b[1;\u{e9}"), None);
+}
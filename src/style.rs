@@ -0,0 +1,448 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+#[cfg(test)]
+mod tests;
+
+use crate::enums::{AnsiSequence, Output};
+use crate::traits::AnsiParseIterator;
+
+use core::fmt::{Display, Formatter, Result as DisplayResult, Write};
+use core::marker::PhantomData;
+use heapless::{
+    consts::{U32, U5},
+    Vec,
+};
+
+///A colour as set by SGR. The sixteen named colours are the ones set by `30`–`37`, `90`–`97`
+///and their background counterparts.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+    ///A colour from the 256 colour palette, set with `38;5;n`.
+    Indexed(u8),
+    ///A true colour, set with `38;2;r;g;b`.
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    ///Returns the named colour for an index into the sixteen colour palette.
+    pub fn from_ansi(index: u8) -> Option<Color> {
+        use Color::*;
+        Some(match index {
+            0 => Black,
+            1 => Red,
+            2 => Green,
+            3 => Yellow,
+            4 => Blue,
+            5 => Magenta,
+            6 => Cyan,
+            7 => White,
+            8 => BrightBlack,
+            9 => BrightRed,
+            10 => BrightGreen,
+            11 => BrightYellow,
+            12 => BrightBlue,
+            13 => BrightMagenta,
+            14 => BrightCyan,
+            15 => BrightWhite,
+            _ => return None,
+        })
+    }
+
+    ///The inverse of [`Color::from_ansi`].
+    pub fn ansi_index(&self) -> Option<u8> {
+        use Color::*;
+        Some(match self {
+            Black => 0,
+            Red => 1,
+            Green => 2,
+            Yellow => 3,
+            Blue => 4,
+            Magenta => 5,
+            Cyan => 6,
+            White => 7,
+            BrightBlack => 8,
+            BrightRed => 9,
+            BrightGreen => 10,
+            BrightYellow => 11,
+            BrightBlue => 12,
+            BrightMagenta => 13,
+            BrightCyan => 14,
+            BrightWhite => 15,
+            Indexed(_) | Rgb(..) => return None,
+        })
+    }
+}
+
+///The pen that text is drawn with. `None` colours are the terminal defaults.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub blink: bool,
+    pub reverse: bool,
+    pub hidden: bool,
+    pub strikethrough: bool,
+}
+
+impl Style {
+    pub fn is_default(&self) -> bool {
+        *self == Style::default()
+    }
+
+    ///Applies the parameters of a single `SetGraphicsMode`, an empty list being a reset.
+    ///Unknown parameters are skipped.
+    pub fn apply_sgr(&mut self, params: &[u8]) {
+        if params.is_empty() {
+            *self = Style::default();
+            return;
+        }
+
+        let mut i = 0;
+        while i < params.len() {
+            match params[i] {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                5 | 6 => self.blink = true,
+                7 => self.reverse = true,
+                8 => self.hidden = true,
+                9 => self.strikethrough = true,
+                22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                23 => self.italic = false,
+                24 => self.underline = false,
+                25 => self.blink = false,
+                27 => self.reverse = false,
+                28 => self.hidden = false,
+                29 => self.strikethrough = false,
+                n @ 30..=37 => self.fg = Color::from_ansi(n - 30),
+                38 => {
+                    let (color, used) = extended_color(&params[i + 1..]);
+                    if color.is_some() {
+                        self.fg = color;
+                    }
+                    i += used;
+                }
+                39 => self.fg = None,
+                n @ 40..=47 => self.bg = Color::from_ansi(n - 40),
+                48 => {
+                    let (color, used) = extended_color(&params[i + 1..]);
+                    if color.is_some() {
+                        self.bg = color;
+                    }
+                    i += used;
+                }
+                49 => self.bg = None,
+                n @ 90..=97 => self.fg = Color::from_ansi(n - 90 + 8),
+                n @ 100..=107 => self.bg = Color::from_ansi(n - 100 + 8),
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+}
+
+impl Style {
+    ///The shortest SGR change that turns `self` into `to`, or `None` when they are the same.
+    ///
+    ///Switching attributes off one by one is weighed against a reset followed by switching on
+    ///what `to` needs, whichever renders shorter wins.
+    pub fn diff(&self, to: &Style) -> Option<SgrDiff> {
+        if self == to {
+            return None;
+        }
+
+        let incremental = incremental_params(self, to);
+        let reset = if to.is_default() {
+            Params::new()
+        } else {
+            let mut params = Params::new();
+            push(&mut params, &[0]);
+            params.extend(incremental_params(&Style::default(), to));
+            params
+        };
+
+        if rendered_len(&reset) < rendered_len(&incremental) {
+            Some(SgrDiff { params: reset })
+        } else {
+            Some(SgrDiff {
+                params: incremental,
+            })
+        }
+    }
+
+    //Like `diff`, but never resets, for when the terminal may have attributes switched on that
+    //`Style` does not track.
+    pub(crate) fn diff_without_reset(&self, to: &Style) -> Option<SgrDiff> {
+        if self == to {
+            return None;
+        }
+        Some(SgrDiff {
+            params: incremental_params(self, to),
+        })
+    }
+
+    ///Writes the escape sequence that turns `self` into `to`, nothing when they are the same.
+    pub fn write_diff<W: Write>(&self, to: &Style, writer: &mut W) -> DisplayResult {
+        match self.diff(to) {
+            Some(diff) => write!(writer, "{}", diff),
+            None => Ok(()),
+        }
+    }
+}
+
+type Params = Vec<u8, U32>;
+
+//A style change needs at most 19 parameters, so pushing can not run out of room.
+fn push(params: &mut Params, values: &[u8]) {
+    params
+        .extend_from_slice(values)
+        .expect("SGR parameter list overflow");
+}
+
+fn push_toggle(params: &mut Params, from: bool, to: bool, on: u8, off: u8) {
+    if from != to {
+        push(params, &[if to { on } else { off }]);
+    }
+}
+
+fn push_color(params: &mut Params, from: Option<Color>, to: Option<Color>, base: u8) {
+    if from == to {
+        return;
+    }
+    match to {
+        None => push(params, &[base + 9]),
+        Some(Color::Indexed(n)) => push(params, &[base + 8, 5, n]),
+        Some(Color::Rgb(r, g, b)) => push(params, &[base + 8, 2, r, g, b]),
+        Some(color) => match color.ansi_index() {
+            Some(n) if n < 8 => push(params, &[base + n]),
+            Some(n) => push(params, &[base + 60 + n - 8]),
+            None => unreachable!(),
+        },
+    }
+}
+
+fn incremental_params(from: &Style, to: &Style) -> Params {
+    let mut params = Params::new();
+
+    //22 switches off both bold and dim, so whichever should stay on has to be set again.
+    if (from.bold && !to.bold) || (from.dim && !to.dim) {
+        push(&mut params, &[22]);
+        push_toggle(&mut params, false, to.bold, 1, 22);
+        push_toggle(&mut params, false, to.dim, 2, 22);
+    } else {
+        push_toggle(&mut params, from.bold, to.bold, 1, 22);
+        push_toggle(&mut params, from.dim, to.dim, 2, 22);
+    }
+
+    push_toggle(&mut params, from.italic, to.italic, 3, 23);
+    push_toggle(&mut params, from.underline, to.underline, 4, 24);
+    push_toggle(&mut params, from.blink, to.blink, 5, 25);
+    push_toggle(&mut params, from.reverse, to.reverse, 7, 27);
+    push_toggle(&mut params, from.hidden, to.hidden, 8, 28);
+    push_toggle(&mut params, from.strikethrough, to.strikethrough, 9, 29);
+    push_color(&mut params, from.fg, to.fg, 30);
+    push_color(&mut params, from.bg, to.bg, 40);
+
+    params
+}
+
+fn rendered_len(params: &[u8]) -> usize {
+    let digits: usize = params
+        .iter()
+        .map(|&p| match p {
+            0..=9 => 1,
+            10..=99 => 2,
+            _ => 3,
+        })
+        .sum();
+    digits + params.len().saturating_sub(1)
+}
+
+///A change of style, as computed by [`Style::diff`]. An empty parameter list is a reset.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SgrDiff {
+    params: Params,
+}
+
+impl SgrDiff {
+    pub fn params(&self) -> &[u8] {
+        &self.params
+    }
+
+    ///Splits the change into `SetGraphicsMode` sequences of at most five parameters each,
+    ///never splitting an extended colour across two of them.
+    pub fn sequences(&self) -> impl Iterator<Item = AnsiSequence> + '_ {
+        let mut rest: &[u8] = &self.params;
+        let mut first = true;
+        core::iter::from_fn(move || {
+            if rest.is_empty() && !first {
+                return None;
+            }
+            first = false;
+
+            let mut chunk: Vec<u8, U5> = Vec::new();
+            while let Some(&param) = rest.first() {
+                let len = match (param, rest.get(1)) {
+                    (38, Some(5)) | (48, Some(5)) => 3,
+                    (38, _) | (48, _) => 5,
+                    _ => 1,
+                };
+                if chunk.extend_from_slice(&rest[..len]).is_err() {
+                    break;
+                }
+                rest = &rest[len..];
+            }
+            Some(AnsiSequence::SetGraphicsMode(chunk))
+        })
+    }
+}
+
+///Writes the change as split by [`SgrDiff::sequences`], so that it parses back.
+impl Display for SgrDiff {
+    fn fmt(&self, formatter: &mut Formatter) -> DisplayResult {
+        for seq in self.sequences() {
+            write!(formatter, "{}", seq)?;
+        }
+        Ok(())
+    }
+}
+
+//Whether `apply_sgr` understands every parameter, so that dropping the sequence in favour of
+//a computed diff loses nothing.
+pub(crate) fn models_sgr(params: &[u8]) -> bool {
+    let mut i = 0;
+    while i < params.len() {
+        match params[i] {
+            0..=9 | 22..=25 | 27..=37 | 39..=47 | 49 | 90..=97 | 100..=107 => i += 1,
+            38 | 48 => match extended_color(&params[i + 1..]) {
+                (Some(_), used) => i += used + 1,
+                (None, _) => return false,
+            },
+            _ => return false,
+        }
+    }
+    true
+}
+
+//Whether the parameters contain a full reset, which also clears attributes `Style` does not
+//track.
+pub(crate) fn resets_sgr(params: &[u8]) -> bool {
+    let mut i = 0;
+    while i < params.len() {
+        match params[i] {
+            0 => return true,
+            38 | 48 => i += extended_color(&params[i + 1..]).1 + 1,
+            _ => i += 1,
+        }
+    }
+    params.is_empty()
+}
+
+//Reads the `5;n` or `2;r;g;b` that follows a `38` or `48`, returning how many parameters were
+//used up.
+fn extended_color(params: &[u8]) -> (Option<Color>, usize) {
+    match params {
+        [5, n, ..] => (Some(Color::Indexed(*n)), 2),
+        [2, r, g, b, ..] => (Some(Color::Rgb(*r, *g, *b)), 4),
+        [5] | [2, ..] => (None, params.len()),
+        _ => (None, 0),
+    }
+}
+
+///Tracks the current pen over a stream of sequences.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct StyleState {
+    style: Style,
+}
+
+impl StyleState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn style(&self) -> Style {
+        self.style
+    }
+
+    ///Updates the pen from `seq`, anything but `SetGraphicsMode` leaves it unchanged.
+    pub fn apply(&mut self, seq: &AnsiSequence) {
+        if let AnsiSequence::SetGraphicsMode(params) = seq {
+            self.style.apply_sgr(params);
+        }
+    }
+}
+
+///Yields every text block of an [`Output`] stream along with the style it is drawn in.
+#[derive(Debug)]
+pub struct StyledIterator<'a, I> {
+    inner: I,
+    state: StyleState,
+    _marker: PhantomData<Output<'a>>,
+}
+
+impl<'a, I> StyledIterator<'a, I>
+where
+    I: Iterator<Item = Output<'a>>,
+{
+    pub fn new(inner: I) -> Self {
+        StyledIterator {
+            inner,
+            state: StyleState::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn state(&self) -> &StyleState {
+        &self.state
+    }
+}
+
+impl<'a, I> Iterator for StyledIterator<'a, I>
+where
+    I: Iterator<Item = Output<'a>>,
+{
+    type Item = (Style, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.inner.next()? {
+                Output::TextBlock(text) => return Some((self.state.style(), text)),
+                Output::Escape(seq) => self.state.apply(&seq),
+            }
+        }
+    }
+}
+
+impl<'a> AnsiParseIterator<'a> {
+    ///Pairs every text block with its style, see [`StyledIterator`].
+    pub fn styled(self) -> StyledIterator<'a, Self> {
+        StyledIterator::new(self)
+    }
+}
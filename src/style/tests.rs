@@ -0,0 +1,250 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+use super::*;
+use crate::traits::AnsiParser;
+use crate::Output;
+
+use std::vec::Vec;
+
+fn style_of(params: &[u8]) -> Style {
+    let mut style = Style::default();
+    style.apply_sgr(params);
+    style
+}
+
+#[test]
+fn test_basic_attributes() {
+    let style = style_of(&[1, 3, 4]);
+    assert!(style.bold && style.italic && style.underline);
+    assert!(!style.dim);
+}
+
+#[test]
+fn test_reset_rules() {
+    let mut style = style_of(&[1, 2, 3, 4, 31, 42]);
+
+    style.apply_sgr(&[22]);
+    assert!(!style.bold && !style.dim);
+    assert!(style.italic);
+
+    style.apply_sgr(&[23, 24]);
+    assert!(!style.italic && !style.underline);
+
+    style.apply_sgr(&[39]);
+    assert_eq!(style.fg, None);
+    assert_eq!(style.bg, Some(Color::Green));
+
+    style.apply_sgr(&[49]);
+    assert!(style.is_default());
+
+    let mut style = style_of(&[1, 31]);
+    style.apply_sgr(&[0]);
+    assert!(style.is_default());
+
+    let mut style = style_of(&[1, 31]);
+    style.apply_sgr(&[]);
+    assert!(style.is_default());
+}
+
+#[test]
+fn test_colors() {
+    assert_eq!(style_of(&[91]).fg, Some(Color::BrightRed));
+    assert_eq!(style_of(&[104]).bg, Some(Color::BrightBlue));
+    assert_eq!(style_of(&[38, 5, 208]).fg, Some(Color::Indexed(208)));
+    assert_eq!(style_of(&[48, 2, 1, 2, 3]).bg, Some(Color::Rgb(1, 2, 3)));
+
+    let style = style_of(&[38, 5, 1, 4]);
+    assert_eq!(style.fg, Some(Color::Indexed(1)));
+    assert!(style.underline);
+}
+
+#[test]
+fn test_truncated_extended_color() {
+    let style = style_of(&[31, 38, 2, 1]);
+    assert_eq!(style.fg, Some(Color::Red));
+}
+
+#[test]
+fn test_state_ignores_other_sequences() {
+    let mut state = StyleState::new();
+    state.apply(&AnsiSequence::SetGraphicsMode(
+        heapless::Vec::from_slice(&[1]).unwrap(),
+    ));
+    state.apply(&AnsiSequence::EraseDisplay);
+    state.apply(&AnsiSequence::CursorPos(1, 1));
+    assert!(state.style().bold);
+}
+
+#[test]
+fn test_styled_iterator() {
+    let runs: Vec<_> = "plain\x1b[1;31mbold red\x1b[22m red\x1b[0m\x1b[2J plain"
+        .ansi_parse()
+        .styled()
+        .collect();
+
+    let bold_red = Style {
+        fg: Some(Color::Red),
+        bold: true,
+        ..Style::default()
+    };
+    let red = Style {
+        fg: Some(Color::Red),
+        ..Style::default()
+    };
+
+    assert_eq!(
+        runs,
+        vec![
+            (Style::default(), "plain"),
+            (bold_red, "bold red"),
+            (red, " red"),
+            (Style::default(), " plain"),
+        ]
+    );
+}
+
+#[test]
+fn test_styled_lolcat() {
+    let runs: Vec<_> = "\u{1b}[38;2;51;254;77mS\u{1b}[39m\u{1b}[38;2;52;254;77mt\u{1b}[39m"
+        .ansi_parse()
+        .styled()
+        .collect();
+
+    assert_eq!(runs.len(), 2);
+    assert_eq!(runs[0].0.fg, Some(Color::Rgb(51, 254, 77)));
+    assert_eq!(runs[1].0.fg, Some(Color::Rgb(52, 254, 77)));
+}
+
+fn diff_string(from: &Style, to: &Style) -> String {
+    let mut buff = String::new();
+    from.write_diff(to, &mut buff).unwrap();
+    buff
+}
+
+fn check_round_trip(from: Style, to: Style) {
+    let mut state = from;
+    if let Some(diff) = from.diff(&to) {
+        for seq in diff.sequences() {
+            if let AnsiSequence::SetGraphicsMode(params) = seq {
+                state.apply_sgr(&params);
+            }
+        }
+    }
+    assert_eq!(state, to);
+
+    // The parser reads at most five parameters per sequence, so what is written has to be split.
+    let mut state = from;
+    let text = diff_string(&from, &to);
+    for part in text.ansi_parse() {
+        match part {
+            Output::Escape(AnsiSequence::SetGraphicsMode(params)) => state.apply_sgr(&params),
+            other => panic!("unexpected output {:?}", other),
+        }
+    }
+    assert_eq!(state, to);
+}
+
+#[test]
+fn test_diff_equal_styles() {
+    let style = style_of(&[1, 31]);
+    assert_eq!(style.diff(&style), None);
+    assert_eq!(diff_string(&style, &style), "");
+}
+
+#[test]
+fn test_diff_incremental() {
+    let from = style_of(&[1, 31]);
+    let to = style_of(&[1, 32]);
+    assert_eq!(diff_string(&from, &to), "\x1b[32m");
+
+    let to = style_of(&[1, 31, 4]);
+    assert_eq!(diff_string(&from, &to), "\x1b[4m");
+}
+
+#[test]
+fn test_diff_prefers_reset_when_shorter() {
+    let from = style_of(&[1, 3, 4, 31, 42]);
+    assert_eq!(diff_string(&from, &Style::default()), "\x1b[m");
+
+    let to = style_of(&[35]);
+    assert_eq!(diff_string(&from, &to), "\x1b[0;35m");
+}
+
+#[test]
+fn test_diff_bold_and_dim_share_a_reset() {
+    let from = style_of(&[1, 2, 4]);
+    let to = style_of(&[2, 4]);
+    assert_eq!(diff_string(&from, &to), "\x1b[22;2m");
+    check_round_trip(from, to);
+}
+
+#[test]
+fn test_diff_colors() {
+    let from = Style::default();
+    assert_eq!(diff_string(&from, &style_of(&[95])), "\x1b[95m");
+    assert_eq!(
+        diff_string(&from, &style_of(&[38, 5, 208])),
+        "\x1b[38;5;208m"
+    );
+    assert_eq!(
+        diff_string(&from, &style_of(&[48, 2, 10, 20, 30])),
+        "\x1b[48;2;10;20;30m"
+    );
+    assert_eq!(
+        diff_string(&style_of(&[33, 4]), &style_of(&[4])),
+        "\x1b[39m"
+    );
+    assert_eq!(diff_string(&style_of(&[33]), &style_of(&[4])), "\x1b[0;4m");
+}
+
+#[test]
+fn test_diff_sequences_keep_colors_whole() {
+    let from = Style::default();
+    let to = style_of(&[1, 3, 4, 38, 2, 1, 2, 3]);
+    let diff = from.diff(&to).unwrap();
+    assert_eq!(diff.params(), &[1, 3, 4, 38, 2, 1, 2, 3]);
+
+    let lens: Vec<_> = diff
+        .sequences()
+        .map(|seq| match seq {
+            AnsiSequence::SetGraphicsMode(params) => params.len(),
+            _ => unreachable!(),
+        })
+        .collect();
+    assert_eq!(lens, vec![3, 5]);
+    assert_eq!(diff.to_string(), "\x1b[1;3;4m\x1b[38;2;1;2;3m");
+    assert_eq!(diff_string(&from, &to), diff.to_string());
+    check_round_trip(from, to);
+}
+
+#[test]
+fn test_diff_reset_sequence() {
+    let diff = style_of(&[1, 3, 4, 7]).diff(&Style::default()).unwrap();
+    assert_eq!(diff.params(), &[] as &[u8]);
+    assert_eq!(
+        diff.sequences().collect::<Vec<_>>(),
+        vec![AnsiSequence::SetGraphicsMode(heapless::Vec::new())]
+    );
+}
+
+#[test]
+fn test_diff_round_trips() {
+    let styles = [
+        Style::default(),
+        style_of(&[1]),
+        style_of(&[2, 9]),
+        style_of(&[1, 2, 3, 4, 5, 7, 8, 9]),
+        style_of(&[31, 44]),
+        style_of(&[91, 104, 1]),
+        style_of(&[38, 5, 3, 48, 5, 200]),
+        style_of(&[38, 2, 1, 2, 3, 48, 2, 4, 5, 6, 1, 2, 3, 4, 5, 7, 8, 9]),
+    ];
+
+    for from in styles.iter() {
+        for to in styles.iter() {
+            check_round_trip(*from, *to);
+        }
+    }
+}
@@ -5,6 +5,8 @@
 use crate::enums::Output;
 use crate::parsers::parse_escape;
 
+use nom::IResult;
+
 pub trait AnsiParser {
     fn ansi_parse(&self) -> AnsiParseIterator<'_>;
 }
@@ -27,49 +29,61 @@ pub struct AnsiParseIterator<'a> {
     dat: &'a str,
 }
 
-impl<'a> Iterator for AnsiParseIterator<'a> {
-    type Item = Output<'a>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.dat.is_empty() {
-            return None;
-        }
+//Splits the next block off the front of `dat`, an escape sequence when `parse` accepts one
+//and text up to the next `ESC` otherwise.
+pub(crate) fn next_block<'a, T>(
+    dat: &mut &'a str,
+    parse: impl Fn(&'a str) -> IResult<&'a str, T>,
+) -> Option<Result<T, &'a str>> {
+    if dat.is_empty() {
+        return None;
+    }
 
-        let pos = self.dat.find('\u{1b}');
-        if let Some(loc) = pos {
-            if loc == 0 {
-                let res = parse_escape(&self.dat[loc..]);
+    let pos = dat.find('\u{1b}');
+    if let Some(loc) = pos {
+        if loc == 0 {
+            let res = parse(&dat[loc..]);
 
-                if let Ok(ret) = res {
-                    self.dat = ret.0;
-                    Some(Output::Escape(ret.1))
-                } else {
-                    let pos = self.dat[(loc + 1)..].find('\u{1b}');
-                    if let Some(loc) = pos {
-                        //Added to because it's based one character ahead
-                        let loc = loc + 1;
+            if let Ok(ret) = res {
+                *dat = ret.0;
+                Some(Ok(ret.1))
+            } else {
+                let pos = dat[(loc + 1)..].find('\u{1b}');
+                if let Some(loc) = pos {
+                    //Added to because it's based one character ahead
+                    let loc = loc + 1;
 
-                        let temp = &self.dat[..loc];
-                        self.dat = &self.dat[loc..];
+                    let temp = &dat[..loc];
+                    *dat = &dat[loc..];
 
-                        Some(Output::TextBlock(temp))
-                    } else {
-                        let temp = self.dat;
-                        self.dat = "";
+                    Some(Err(temp))
+                } else {
+                    let temp = *dat;
+                    *dat = "";
 
-                        Some(Output::TextBlock(temp))
-                    }
+                    Some(Err(temp))
                 }
-            } else {
-                let temp = &self.dat[..loc];
-                self.dat = &self.dat[loc..];
-
-                Some(Output::TextBlock(temp))
             }
         } else {
-            let temp = self.dat;
-            self.dat = "";
-            Some(Output::TextBlock(temp))
+            let temp = &dat[..loc];
+            *dat = &dat[loc..];
+
+            Some(Err(temp))
         }
+    } else {
+        let temp = *dat;
+        *dat = "";
+        Some(Err(temp))
+    }
+}
+
+impl<'a> Iterator for AnsiParseIterator<'a> {
+    type Item = Output<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(match next_block(&mut self.dat, parse_escape)? {
+            Ok(seq) => Output::Escape(seq),
+            Err(text) => Output::TextBlock(text),
+        })
     }
 }
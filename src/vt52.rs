@@ -0,0 +1,187 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+#[cfg(test)]
+mod tests;
+
+use crate::enums::AnsiSequence;
+use crate::parsers::parse_escape;
+use crate::traits::next_block;
+
+use core::fmt::{Display, Formatter, Result as DisplayResult};
+use nom::*;
+
+//`ESC Y` sends the line and column as a character each, offset by a space.
+const COORD_OFFSET: u32 = ' ' as u32;
+const COORD_MAX: u32 = '~' as u32 - COORD_OFFSET;
+
+///The escape sequences of a VT100 in VT52 mode, entered with [`AnsiSequence::SetVT52`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Vt52Sequence {
+    CursorUp,
+    CursorDown,
+    CursorRight,
+    CursorLeft,
+    ///Draws with the VT52 graphics set, much like DEC Special Graphics.
+    EnterGraphicsMode,
+    ExitGraphicsMode,
+    CursorHome,
+    ReverseLineFeed,
+    EraseToEndOfScreen,
+    EraseToEndOfLine,
+    ///The line and column, counting from 0 and at most 94 as that is all `ESC Y` can send.
+    CursorPos(u32, u32),
+    Identify,
+    SetAlternateKeypad,
+    SetNumericKeypad,
+    ///Leaves VT52 mode, after which the terminal takes ANSI sequences again.
+    EnterAnsiMode,
+}
+
+impl Display for Vt52Sequence {
+    fn fmt(&self, formatter: &mut Formatter) -> DisplayResult {
+        write!(formatter, "\u{1b}")?;
+
+        use Vt52Sequence::*;
+        match self {
+            CursorUp => write!(formatter, "A"),
+            CursorDown => write!(formatter, "B"),
+            CursorRight => write!(formatter, "C"),
+            CursorLeft => write!(formatter, "D"),
+            EnterGraphicsMode => write!(formatter, "F"),
+            ExitGraphicsMode => write!(formatter, "G"),
+            CursorHome => write!(formatter, "H"),
+            ReverseLineFeed => write!(formatter, "I"),
+            EraseToEndOfScreen => write!(formatter, "J"),
+            EraseToEndOfLine => write!(formatter, "K"),
+            CursorPos(line, col) => {
+                let coord =
+                    |n: u32| core::char::from_u32(n.min(COORD_MAX) + COORD_OFFSET).unwrap_or('~');
+                write!(formatter, "Y{}{}", coord(*line), coord(*col))
+            }
+            Identify => write!(formatter, "Z"),
+            SetAlternateKeypad => write!(formatter, "="),
+            SetNumericKeypad => write!(formatter, ">"),
+            EnterAnsiMode => write!(formatter, "<"),
+        }
+    }
+}
+
+named!(
+    coord<&str, u32>,
+    map!(
+        verify!(anychar, |c: char| (' '..='~').contains(&c)),
+        |c: char| c as u32 - COORD_OFFSET
+    )
+);
+
+named!(
+    cursor_pos<&str, Vt52Sequence>,
+    do_parse!(
+        char!('Y')  >>
+        line: coord >>
+        col: coord  >>
+        (Vt52Sequence::CursorPos(line, col))
+    )
+);
+
+named!(
+    combined<&str, Vt52Sequence>,
+    alt!(
+          value!(Vt52Sequence::CursorUp, char!('A'))
+        | value!(Vt52Sequence::CursorDown, char!('B'))
+        | value!(Vt52Sequence::CursorRight, char!('C'))
+        | value!(Vt52Sequence::CursorLeft, char!('D'))
+        | value!(Vt52Sequence::EnterGraphicsMode, char!('F'))
+        | value!(Vt52Sequence::ExitGraphicsMode, char!('G'))
+        | value!(Vt52Sequence::CursorHome, char!('H'))
+        | value!(Vt52Sequence::ReverseLineFeed, char!('I'))
+        | value!(Vt52Sequence::EraseToEndOfScreen, char!('J'))
+        | value!(Vt52Sequence::EraseToEndOfLine, char!('K'))
+        | cursor_pos
+        | value!(Vt52Sequence::Identify, char!('Z'))
+        | value!(Vt52Sequence::SetAlternateKeypad, char!('='))
+        | value!(Vt52Sequence::SetNumericKeypad, char!('>'))
+        | value!(Vt52Sequence::EnterAnsiMode, char!('<'))
+    )
+);
+
+named!(
+    pub parse_vt52<&str, Vt52Sequence>,
+    do_parse!(
+        tag!("\u{1b}")    >>
+        seq: combined     >>
+        (seq)
+    )
+);
+
+///What [`ModalParseIterator`] splits its input into.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModalOutput<'a> {
+    TextBlock(&'a str),
+    Escape(AnsiSequence),
+    Vt52(Vt52Sequence),
+}
+
+impl<'a> Display for ModalOutput<'a> {
+    fn fmt(&self, formatter: &mut Formatter) -> DisplayResult {
+        use ModalOutput::*;
+        match self {
+            TextBlock(txt) => write!(formatter, "{}", txt),
+            Escape(seq) => write!(formatter, "{}", seq),
+            Vt52(seq) => write!(formatter, "{}", seq),
+        }
+    }
+}
+
+///Parses like [`AnsiParseIterator`](crate::AnsiParseIterator), but switches to the VT52 grammar
+///after [`AnsiSequence::SetVT52`] and back to ANSI after [`Vt52Sequence::EnterAnsiMode`].
+#[derive(Debug)]
+pub struct ModalParseIterator<'a> {
+    dat: &'a str,
+    vt52: bool,
+}
+
+impl<'a> ModalParseIterator<'a> {
+    ///Starts out in ANSI mode.
+    pub fn new(dat: &'a str) -> Self {
+        ModalParseIterator { dat, vt52: false }
+    }
+
+    ///Sets whether parsing starts out in VT52 mode, for input that carries on from an earlier
+    ///iterator that ended in it.
+    pub fn with_vt52(mut self, vt52: bool) -> Self {
+        self.vt52 = vt52;
+        self
+    }
+
+    ///Whether what comes next is parsed as VT52.
+    pub fn is_vt52(&self) -> bool {
+        self.vt52
+    }
+}
+
+impl<'a> Iterator for ModalParseIterator<'a> {
+    type Item = ModalOutput<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.vt52 {
+            Some(match next_block(&mut self.dat, parse_vt52)? {
+                Ok(seq) => {
+                    self.vt52 = seq != Vt52Sequence::EnterAnsiMode;
+                    ModalOutput::Vt52(seq)
+                }
+                Err(text) => ModalOutput::TextBlock(text),
+            })
+        } else {
+            Some(match next_block(&mut self.dat, parse_escape)? {
+                Ok(seq) => {
+                    self.vt52 = seq == AnsiSequence::SetVT52;
+                    ModalOutput::Escape(seq)
+                }
+                Err(text) => ModalOutput::TextBlock(text),
+            })
+        }
+    }
+}
@@ -0,0 +1,107 @@
+/*
+ *   Copyright (c) 2022
+ *   All rights reserved.
+ */
+use super::*;
+
+use std::string::ToString;
+use std::vec::Vec;
+
+macro_rules! test_vt52 {
+    ($name:ident, $string:expr, $seq:expr) => {
+        #[test]
+        fn $name() {
+            let (rest, seq) = parse_vt52($string).unwrap();
+            assert_eq!(rest, "");
+            assert_eq!(seq, $seq);
+            assert_eq!(seq.to_string(), $string);
+        }
+    };
+}
+
+test_vt52!(cursor_up, "\u{1b}A", Vt52Sequence::CursorUp);
+test_vt52!(cursor_down, "\u{1b}B", Vt52Sequence::CursorDown);
+test_vt52!(cursor_right, "\u{1b}C", Vt52Sequence::CursorRight);
+test_vt52!(cursor_left, "\u{1b}D", Vt52Sequence::CursorLeft);
+test_vt52!(enter_graphics, "\u{1b}F", Vt52Sequence::EnterGraphicsMode);
+test_vt52!(exit_graphics, "\u{1b}G", Vt52Sequence::ExitGraphicsMode);
+test_vt52!(cursor_home, "\u{1b}H", Vt52Sequence::CursorHome);
+test_vt52!(reverse_line_feed, "\u{1b}I", Vt52Sequence::ReverseLineFeed);
+test_vt52!(erase_screen, "\u{1b}J", Vt52Sequence::EraseToEndOfScreen);
+test_vt52!(erase_line, "\u{1b}K", Vt52Sequence::EraseToEndOfLine);
+test_vt52!(cursor_pos, "\u{1b}Y%*", Vt52Sequence::CursorPos(5, 10));
+test_vt52!(
+    cursor_pos_origin,
+    "\u{1b}Y  ",
+    Vt52Sequence::CursorPos(0, 0)
+);
+test_vt52!(cursor_pos_max, "\u{1b}Y~~", Vt52Sequence::CursorPos(94, 94));
+test_vt52!(identify, "\u{1b}Z", Vt52Sequence::Identify);
+test_vt52!(
+    alternate_keypad,
+    "\u{1b}=",
+    Vt52Sequence::SetAlternateKeypad
+);
+test_vt52!(numeric_keypad, "\u{1b}>", Vt52Sequence::SetNumericKeypad);
+test_vt52!(enter_ansi, "\u{1b}<", Vt52Sequence::EnterAnsiMode);
+
+#[test]
+fn test_cursor_pos_display_clamps() {
+    assert_eq!(Vt52Sequence::CursorPos(200, 3).to_string(), "\u{1b}Y~#");
+}
+
+#[test]
+fn test_invalid_sequences() {
+    assert!(parse_vt52("\u{1b}[A").is_err());
+    assert!(parse_vt52("\u{1b}Y\u{7f} ").is_err());
+    assert!(parse_vt52("\u{1b}E").is_err());
+}
+
+#[test]
+fn test_switches_grammar() {
+    let parts: Vec<_> =
+        ModalParseIterator::new("\x1b[A\x1b[?2l\x1b[A\x1bA\x1bY!!x\x1b<\x1bA\x1b[A").collect();
+    assert_eq!(
+        parts,
+        vec![
+            ModalOutput::Escape(AnsiSequence::CursorUp(1)),
+            ModalOutput::Escape(AnsiSequence::SetVT52),
+            ModalOutput::TextBlock("\x1b[A"),
+            ModalOutput::Vt52(Vt52Sequence::CursorUp),
+            ModalOutput::Vt52(Vt52Sequence::CursorPos(1, 1)),
+            ModalOutput::TextBlock("x"),
+            ModalOutput::Vt52(Vt52Sequence::EnterAnsiMode),
+            ModalOutput::TextBlock("\x1bA"),
+            ModalOutput::Escape(AnsiSequence::CursorUp(1)),
+        ]
+    );
+}
+
+#[test]
+fn test_mode_is_tracked() {
+    let mut iter = ModalParseIterator::new("\x1b[?2lab");
+    assert!(!iter.is_vt52());
+    iter.next();
+    assert!(iter.is_vt52());
+
+    let mut iter = ModalParseIterator::new("\x1bH\x1b<").with_vt52(true);
+    assert_eq!(
+        iter.next(),
+        Some(ModalOutput::Vt52(Vt52Sequence::CursorHome))
+    );
+    assert_eq!(
+        iter.next(),
+        Some(ModalOutput::Vt52(Vt52Sequence::EnterAnsiMode))
+    );
+    assert!(!iter.is_vt52());
+    assert_eq!(iter.next(), None);
+}
+
+#[test]
+fn test_round_trip() {
+    let input = "a\x1b[?2l\x1bY#$b\x1bK\x1b<\x1b[2Jc";
+    let out: std::string::String = ModalParseIterator::new(input)
+        .map(|part| part.to_string())
+        .collect();
+    assert_eq!(out, input);
+}